@@ -0,0 +1,131 @@
+/*!
+Books of the Bible
+
+Chapter titles, corpora and section tables don't always name a book the same way: one file has
+`Psalms 83:18`, another `Ps. 83:18`. This module reduces a name to the key of its book, so that every
+name or abbreviation of a book compares equal and different books never do. The abbreviations are
+those of the New World Translation along with other common ones.
+*/
+
+/// The key of each book, followed by its known abbreviations, all written as [book_key] compares them.
+const BOOKS: [(&str, &[&str]); 66] = [
+    ("genesis", &["ge", "gen", "gn"]),
+    ("exodus", &["ex", "exod"]),
+    ("leviticus", &["le", "lev", "lv"]),
+    ("numbers", &["nu", "num", "nm"]),
+    ("deuteronomy", &["de", "deut", "dt"]),
+    ("joshua", &["jos", "josh"]),
+    ("judges", &["jg", "judg", "jdg"]),
+    ("ruth", &["ru", "rth"]),
+    ("1samuel", &["1sa", "1sam"]),
+    ("2samuel", &["2sa", "2sam"]),
+    ("1kings", &["1ki", "1kgs"]),
+    ("2kings", &["2ki", "2kgs"]),
+    ("1chronicles", &["1ch", "1chr", "1chron"]),
+    ("2chronicles", &["2ch", "2chr", "2chron"]),
+    ("ezra", &["ezr"]),
+    ("nehemiah", &["ne", "neh"]),
+    ("esther", &["es", "esth"]),
+    ("job", &[]),
+    ("psalms", &["ps", "psa", "psalm", "pss"]),
+    ("proverbs", &["pr", "prov"]),
+    ("ecclesiastes", &["ec", "eccl", "eccles"]),
+    ("songofsolomon", &["ca", "song", "songofsongs", "sg", "ss"]),
+    ("isaiah", &["isa", "is"]),
+    ("jeremiah", &["jer"]),
+    ("lamentations", &["la", "lam"]),
+    ("ezekiel", &["eze", "ezek"]),
+    ("daniel", &["da", "dan"]),
+    ("hosea", &["ho", "hos"]),
+    ("joel", &["joe"]),
+    ("amos", &["am"]),
+    ("obadiah", &["ob", "obad"]),
+    ("jonah", &["jon", "jnh"]),
+    ("micah", &["mic"]),
+    ("nahum", &["na", "nah"]),
+    ("habakkuk", &["hab"]),
+    ("zephaniah", &["zep", "zeph"]),
+    ("haggai", &["hag"]),
+    ("zechariah", &["zec", "zech"]),
+    ("malachi", &["mal"]),
+    ("matthew", &["mt", "matt"]),
+    ("mark", &["mr", "mk", "mrk"]),
+    ("luke", &["lu", "lk", "luk"]),
+    ("john", &["joh", "jn"]),
+    ("acts", &["ac"]),
+    ("romans", &["ro", "rom"]),
+    ("1corinthians", &["1co", "1cor"]),
+    ("2corinthians", &["2co", "2cor"]),
+    ("galatians", &["ga", "gal"]),
+    ("ephesians", &["eph"]),
+    ("philippians", &["php", "phil", "pp"]),
+    ("colossians", &["col"]),
+    ("1thessalonians", &["1th", "1thess"]),
+    ("2thessalonians", &["2th", "2thess"]),
+    ("1timothy", &["1ti", "1tim"]),
+    ("2timothy", &["2ti", "2tim"]),
+    ("titus", &["tit"]),
+    ("philemon", &["phm", "philem"]),
+    ("hebrews", &["heb"]),
+    ("james", &["jas"]),
+    ("1peter", &["1pe", "1pet"]),
+    ("2peter", &["2pe", "2pet"]),
+    ("1john", &["1jo", "1jn"]),
+    ("2john", &["2jo", "2jn"]),
+    ("3john", &["3jo", "3jn"]),
+    ("jude", &["jud"]),
+    ("revelation", &["re", "rev"]),
+];
+
+/**
+Returns the key a book is matched by.
+
+The name is compared in lower case without spaces or periods, so `1 John` and `1john` are the same.
+A known name or abbreviation of a book gives the key of that book, such as `psalms` for `Ps.`, and
+any other name is kept as it is compared.
+*/
+pub fn book_key(book: &str) -> String {
+    let key: String = book
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '.')
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    match BOOKS
+        .iter()
+        .find(|(name, abbreviations)| *name == key || abbreviations.contains(&key.as_str()))
+    {
+        Some((name, _)) => name.to_string(),
+        None => key,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_book_key() {
+        assert_eq!(book_key("Ps."), "psalms");
+        assert_eq!(book_key("Psalm"), "psalms");
+        assert_eq!(book_key("1 John"), "1john");
+        assert_eq!(book_key("1 Jo."), "1john");
+        assert_eq!(book_key("Phil."), "philippians");
+        assert_eq!(book_key("Philem."), "philemon");
+        assert_eq!(book_key("Tobit"), "tobit");
+    }
+
+    #[test]
+    fn test_abbreviations_are_unique() {
+        let mut keys: Vec<&str> = BOOKS
+            .iter()
+            .flat_map(|(name, abbreviations)| {
+                std::iter::once(*name).chain(abbreviations.iter().copied())
+            })
+            .collect();
+        let count: usize = keys.len();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), count);
+    }
+}
@@ -0,0 +1,248 @@
+/*!
+Chapter lists
+
+Writes the chapters of a video file in formats other tools read:
+
+- [FFMETADATA](https://ffmpeg.org/ffmpeg-formats.html#Metadata-1), written with each chapter's own
+  time base and ticks so muxing it back with ffmpeg reproduces the same markers.
+- A [CUE sheet](https://en.wikipedia.org/wiki/Cue_sheet_(computing)), one track per verse.
+- CSV with the index, title, start and end of each chapter in seconds.
+- Plain `m:ss Title` lines, as used for chapters in video descriptions.
+*/
+
+use crate::ffwrappers::metadata;
+use crate::ffwrappers::probe::{Chapter, Root};
+use std::{path::Path, str::FromStr};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The chapter format `{format:?}` is not supported")]
+    UnknownFormat { format: String },
+    #[error("A CUE sheet holds at most 99 tracks, the file has {chapters} chapters")]
+    TooManyTracks { chapters: usize },
+}
+
+/// The most tracks a CUE sheet can hold.
+const MAX_TRACKS: usize = 99;
+
+/// The formats a chapter list can be written as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChapterFormat {
+    Ffmetadata,
+    Cue,
+    Csv,
+    Youtube,
+}
+
+/// The names of the formats, as accepted on the command line.
+pub const FORMATS: [&str; 4] = ["ffmetadata", "cue", "csv", "youtube"];
+
+impl FromStr for ChapterFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ffmetadata" => Ok(ChapterFormat::Ffmetadata),
+            "cue" => Ok(ChapterFormat::Cue),
+            "csv" => Ok(ChapterFormat::Csv),
+            "youtube" => Ok(ChapterFormat::Youtube),
+            _ => Err(Error::UnknownFormat {
+                format: s.to_string(),
+            }),
+        }
+    }
+}
+
+/**
+Returns the chapters of `root` in the given format.
+
+The `path` of the source names the file in a CUE sheet.
+
+# Example
+```rust, ignore
+let chapters: Root = Root::new("nwt_43_Joh_ASL_03_r720P.mp4")?;
+let list: String = chapters::export(&chapters, Path::new("nwt_43_Joh_ASL_03_r720P.mp4"), ChapterFormat::Youtube)?;
+```
+*/
+pub fn export(root: &Root, path: &Path, format: ChapterFormat) -> Result<String, Error> {
+    match format {
+        ChapterFormat::Ffmetadata => Ok(ffmetadata(&root.chapters)),
+        ChapterFormat::Cue => cue(&root.chapters, path),
+        ChapterFormat::Csv => Ok(csv(&root.chapters)),
+        ChapterFormat::Youtube => Ok(youtube(&root.chapters)),
+    }
+}
+
+/// Returns the time as `m:ss`, or `h:mm:ss` past the first hour.
+pub fn clock(seconds: f64) -> String {
+    let total: u64 = seconds.max(0.0) as u64;
+    let (hours, minutes, seconds) = (total / 3600, total / 60 % 60, total % 60);
+
+    match hours {
+        0 => format!("{}:{:02}", minutes, seconds),
+        _ => format!("{}:{:02}:{:02}", hours, minutes, seconds),
+    }
+}
+
+/**
+Returns the chapters as an FFMETADATA file.
+
+Chapters keep their own time base and ticks. A chapter without a time base is written in
+milliseconds from its `start_time` and `end_time`.
+*/
+fn ffmetadata(chapters: &[Chapter]) -> String {
+    let mut file = String::from(";FFMETADATA1\n");
+
+    for chapter in chapters {
+        let (time_base, start, end) = match chapter.time_base.is_empty() {
+            false => (chapter.time_base.clone(), chapter.start, chapter.end),
+            true => {
+                let (start, end) = chapter.times();
+                (
+                    String::from("1/1000"),
+                    start.to_ticks("1/1000").unwrap_or_default(),
+                    end.to_ticks("1/1000").unwrap_or_default(),
+                )
+            }
+        };
+        file.push_str(&format!(
+            "\n[CHAPTER]\nTIMEBASE={}\nSTART={}\nEND={}\ntitle={}\n",
+            time_base,
+            start,
+            end,
+            metadata::escape(&chapter.tags.title)
+        ));
+    }
+
+    file
+}
+
+/// Returns the chapters as a CUE sheet, one track per chapter indexed in `mm:ss:ff` at 75 frames a second.
+fn cue(chapters: &[Chapter], path: &Path) -> Result<String, Error> {
+    if chapters.len() > MAX_TRACKS {
+        return Err(Error::TooManyTracks {
+            chapters: chapters.len(),
+        });
+    }
+
+    let name: String = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut file: String = format!("FILE \"{}\" WAVE\n", name.replace('"', "'"));
+
+    for (i, chapter) in chapters.iter().enumerate() {
+        let frames: u64 = chapter
+            .times()
+            .0
+            .to_ticks("1/75")
+            .unwrap_or_default()
+            .max(0) as u64;
+        file.push_str(&format!(
+            "  TRACK {:02} AUDIO\n    TITLE \"{}\"\n    INDEX 01 {:02}:{:02}:{:02}\n",
+            i + 1,
+            chapter.tags.title.replace('"', "'"),
+            frames / 75 / 60,
+            frames / 75 % 60,
+            frames % 75
+        ));
+    }
+
+    Ok(file)
+}
+
+/// Returns the chapters as CSV with a header row.
+fn csv(chapters: &[Chapter]) -> String {
+    let mut file = String::from("index,title,start,end\n");
+
+    for (i, chapter) in chapters.iter().enumerate() {
+        let title: &str = &chapter.tags.title;
+        let title: String = match title.contains([',', '"', '\n']) {
+            true => format!("\"{}\"", title.replace('"', "\"\"")),
+            false => title.to_string(),
+        };
+        let (start, end) = chapter.times();
+        file.push_str(&format!("{},{},{},{}\n", i + 1, title, start, end));
+    }
+
+    file
+}
+
+/// Returns the chapters as `m:ss Title` lines.
+fn youtube(chapters: &[Chapter]) -> String {
+    chapters
+        .iter()
+        .map(|c| format!("{} {}\n", clock(c.times().0.seconds()), c.tags.title))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffwrappers::probe::fixtures::init_struct_1;
+
+    #[test]
+    fn test_clock() {
+        assert_eq!(clock(5.2), "0:05");
+        assert_eq!(clock(212.9), "3:32");
+        assert_eq!(clock(3725.0), "1:02:05");
+    }
+
+    #[test]
+    fn test_ffmetadata() {
+        // Chapters without a time base are written in milliseconds from their times.
+        let mut root = init_struct_1();
+        root.chapters[1].time_base = String::new();
+        root.chapters.truncate(2);
+        assert_eq!(
+            ffmetadata(&root.chapters),
+            ";FFMETADATA1\n\
+             \n[CHAPTER]\nTIMEBASE=1/1000\nSTART=197597\nEND=226259\ntitle=John 3:16\n\
+             \n[CHAPTER]\nTIMEBASE=1/1000\nSTART=226259\nEND=241908\ntitle=John 3:17\n"
+        );
+    }
+
+    #[test]
+    fn test_cue() {
+        let mut root = init_struct_1();
+        root.chapters[1].tags.title = String::from("John 3:17, \"Rabbi\"");
+        let file = export(&root, Path::new("/videos/John.mp4"), ChapterFormat::Cue).unwrap();
+        assert_eq!(
+            file,
+            "FILE \"John.mp4\" WAVE\n\
+             \x20 TRACK 01 AUDIO\n    TITLE \"John 3:16\"\n    INDEX 01 03:17:45\n\
+             \x20 TRACK 02 AUDIO\n    TITLE \"John 3:17, 'Rabbi'\"\n    INDEX 01 03:46:19\n\
+             \x20 TRACK 03 AUDIO\n    TITLE \"John 3:25\"\n    INDEX 01 05:58:49\n\
+             \x20 TRACK 04 AUDIO\n    TITLE \"John 3:26\"\n    INDEX 01 06:14:56\n"
+        );
+
+        let root = Root {
+            chapters: vec![Chapter::default(); 100],
+            ..Default::default()
+        };
+        assert!(export(&root, Path::new("a.mp4"), ChapterFormat::Cue).is_err());
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut root = init_struct_1();
+        root.chapters[1].tags.title = String::from("John 3:17, \"Rabbi\"");
+        assert_eq!(
+            csv(&root.chapters),
+            "index,title,start,end\n\
+             1,John 3:16,197.597,226.259\n\
+             2,\"John 3:17, \"\"Rabbi\"\"\",226.259,241.908\n\
+             3,John 3:25,358.658,374.741\n\
+             4,John 3:26,374.741,394.561\n"
+        );
+    }
+
+    #[test]
+    fn test_youtube() {
+        assert_eq!(
+            youtube(&init_struct_1().chapters),
+            "3:17 John 3:16\n3:46 John 3:17\n5:58 John 3:25\n6:14 John 3:26\n"
+        );
+    }
+}
@@ -0,0 +1,269 @@
+/*!
+Chapter validation
+
+ScriptX trusts the chapters ffprobe reports when it cuts. This module looks them over for the
+problems that would make a cut wrong: chapters that overlap or are out of order, gaps between
+verses, duplicate or malformed titles, ticks that disagree with the reported times, missing verses
+and chapters running past the end of the file.
+*/
+
+use crate::ffwrappers::probe::{self, Chapter, Root};
+use crate::timestamp::Timestamp;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Found {count} problem(s) with the chapters")]
+    Problems { count: usize },
+}
+
+/// How far apart two chapter boundaries may be and still count as the same.
+const TOLERANCE: Timestamp = Timestamp::MILLISECOND;
+
+/// The kinds of problems a chapter can have.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemKind {
+    /// The chapter starts before the previous one.
+    OutOfOrder,
+    /// The chapter starts before the previous one ends.
+    Overlap,
+    /// The chapter starts after the previous one ends.
+    Gap,
+    /// Another chapter has the same title.
+    DuplicateTitle,
+    /// The title doesn't follow the "Book C:V" pattern.
+    BadTitle,
+    /// The `start` or `end` ticks in the `time_base` disagree with `start_time` or `end_time`.
+    TickMismatch,
+    /// A verse between the first and last verse of a chapter has no chapter of its own.
+    MissingVerse,
+    /// The chapter ends after the end of the file.
+    PastDuration,
+}
+
+/// A problem found with the chapters.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Problem {
+    /// What kind of problem it is.
+    pub kind: ProblemKind,
+    /// The `id` of the chapter with the problem, `None` for a verse that has no chapter.
+    pub chapter: Option<i64>,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl Problem {
+    fn new(kind: ProblemKind, chapter: &Chapter, message: String) -> Problem {
+        Problem {
+            kind,
+            chapter: Some(chapter.id),
+            message: format!(
+                "Chapter {} ({}): {}",
+                chapter.id, chapter.tags.title, message
+            ),
+        }
+    }
+}
+
+/// Returns every problem found with the chapters of `root`, in the order of the chapters.
+pub fn check(root: &Root) -> Vec<Problem> {
+    let mut problems: Vec<Problem> = Vec::new();
+    let duration: Option<Timestamp> = root.duration();
+    let mut titles: HashMap<&str, i64> = HashMap::new();
+
+    for (i, chapter) in root.chapters.iter().enumerate() {
+        let (start, end) = chapter.times();
+
+        if let Some(previous) = i.checked_sub(1).map(|p| &root.chapters[p]) {
+            let (previous_start, previous_end) = previous.times();
+            if start < previous_start {
+                problems.push(Problem::new(
+                    ProblemKind::OutOfOrder,
+                    chapter,
+                    format!("starts at {}s, before chapter {}", start, previous.id),
+                ));
+            } else if start < previous_end - TOLERANCE {
+                problems.push(Problem::new(
+                    ProblemKind::Overlap,
+                    chapter,
+                    format!(
+                        "starts at {}s, before chapter {} ends at {}s",
+                        start, previous.id, previous_end
+                    ),
+                ));
+            } else if start > previous_end + TOLERANCE {
+                problems.push(Problem::new(
+                    ProblemKind::Gap,
+                    chapter,
+                    format!(
+                        "starts {:.3}s after chapter {} ends",
+                        start - previous_end,
+                        previous.id
+                    ),
+                ));
+            }
+        }
+
+        match titles.get(chapter.tags.title.as_str()) {
+            Some(first) => problems.push(Problem::new(
+                ProblemKind::DuplicateTitle,
+                chapter,
+                format!("has the same title as chapter {}", first),
+            )),
+            None => {
+                titles.insert(&chapter.tags.title, chapter.id);
+            }
+        }
+
+        if probe::split_title(&chapter.tags.title).is_none() {
+            problems.push(Problem::new(
+                ProblemKind::BadTitle,
+                chapter,
+                String::from("the title doesn't follow the \"Book C:V\" pattern"),
+            ));
+        }
+
+        if chapter.tick_mismatch() {
+            for (name, ticks, time) in [("start", chapter.start, start), ("end", chapter.end, end)]
+            {
+                match Timestamp::from_ticks(ticks, &chapter.time_base) {
+                    Ok(from_ticks) if (from_ticks - time).abs() > Timestamp::MICROSECOND => {
+                        problems.push(Problem::new(
+                            ProblemKind::TickMismatch,
+                            chapter,
+                            format!(
+                                "{} {} in {} is {}s but {}_time is {}s",
+                                name, ticks, chapter.time_base, from_ticks, name, time
+                            ),
+                        ))
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        if let Some(d) = duration {
+            if end > d + TOLERANCE {
+                problems.push(Problem::new(
+                    ProblemKind::PastDuration,
+                    chapter,
+                    format!("ends at {}s, past the end of the file at {}s", end, d),
+                ));
+            }
+        }
+    }
+
+    problems.append(&mut missing_verses(&root.chapters));
+    problems
+}
+
+/// Returns a problem for every verse missing between the first and last verse of each book and chapter.
+fn missing_verses(chapters: &[Chapter]) -> Vec<Problem> {
+    let mut verses: BTreeMap<(String, u32), Vec<u32>> = BTreeMap::new();
+    for chapter in chapters {
+        if let Some((book, number, verse)) = probe::split_title(&chapter.tags.title) {
+            verses
+                .entry((book.to_string(), number))
+                .or_default()
+                .push(verse);
+        }
+    }
+
+    let mut problems: Vec<Problem> = Vec::new();
+    for ((book, number), mut found) in verses {
+        found.sort_unstable();
+        for pair in found.windows(2) {
+            for verse in pair[0] + 1..pair[1] {
+                problems.push(Problem {
+                    kind: ProblemKind::MissingVerse,
+                    chapter: None,
+                    message: format!("{} {}:{} has no chapter", book, number, verse),
+                });
+            }
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffwrappers::probe::fixtures::{chapter, root};
+    use crate::ffwrappers::probe::Format;
+
+    fn kinds(problems: &[Problem]) -> Vec<ProblemKind> {
+        problems.iter().map(|p| p.kind).collect()
+    }
+
+    #[test]
+    fn test_check_clean() {
+        let root = Root {
+            chapters: vec![
+                chapter(16, "197.597", "226.259", "John 3:16"),
+                chapter(17, "226.259", "241.908", "John 3:17"),
+            ],
+            format: Some(Format {
+                duration: String::from("400.000000"),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(check(&root).is_empty());
+    }
+
+    #[test]
+    fn test_check_tick_mismatch() {
+        // A start tick left at the start of the previous chapter.
+        let mut mismatched = chapter(17, "226.259", "241.908", "John 3:17");
+        mismatched.start = 197597;
+        let root = root(vec![
+            chapter(16, "197.597", "226.259", "John 3:16"),
+            mismatched,
+        ]);
+        let problems = check(&root);
+        assert_eq!(kinds(&problems), vec![ProblemKind::TickMismatch]);
+        assert_eq!(problems[0].chapter, Some(17));
+    }
+
+    #[test]
+    fn test_check_problems() {
+        let root = Root {
+            chapters: vec![
+                chapter(1, "0", "10", "John 3:1"),
+                chapter(2, "9", "20", "John 3:2"),
+                chapter(5, "21", "30", "John 3:5"),
+                chapter(6, "30", "41", "John 3:5"),
+                chapter(7, "41", "50", "Outro"),
+            ],
+            format: Some(Format {
+                duration: String::from("45.000000"),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            kinds(&check(&root)),
+            vec![
+                ProblemKind::Overlap,
+                ProblemKind::Gap,
+                ProblemKind::DuplicateTitle,
+                ProblemKind::BadTitle,
+                ProblemKind::PastDuration,
+                ProblemKind::MissingVerse,
+                ProblemKind::MissingVerse,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_out_of_order() {
+        let root = root(vec![
+            chapter(2, "10", "20", "John 3:2"),
+            chapter(1, "0", "10", "John 3:1"),
+        ]);
+        assert_eq!(kinds(&check(&root)), vec![ProblemKind::OutOfOrder]);
+    }
+}
@@ -0,0 +1,341 @@
+/*!
+Verse texts from a local corpus
+
+Loads the text of the Bible from a file on disk, keyed by book, chapter and verse, so it can be
+shown as subtitles while each verse is signed. Three layouts are read:
+
+- JSON, either a list of `{"book": "John", "chapter": 3, "verse": 16, "text": "..."}` objects or
+  nested objects such as `{"John": {"3": {"16": "..."}}}`.
+- CSV with the columns `book,chapter,verse,text`, with an optional header row.
+- Lines of a reference and its text separated by a tab, such as `John 3:16<TAB>...`. Blank lines and
+  lines starting with `#` are skipped.
+
+Books are matched by their [book_key], so `1 John` matches `1john` and `Ps.` matches `Psalms`. The corpus can
+also be searched for a phrase, to find a verse when only its wording is remembered.
+*/
+
+use crate::books::book_key;
+use crate::ffwrappers::probe;
+use serde_json::Value;
+use std::{collections::HashMap, fs, io, path::Path};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Unable to read the corpus: {0}")]
+    Io(#[from] io::Error),
+    #[error("The corpus is not valid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Entry {entry} of the corpus is not a book, chapter, verse and text")]
+    Malformed { entry: usize },
+}
+
+/// A verse of the corpus.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Verse {
+    /// The book as it is named in the corpus.
+    pub book: String,
+    /// The chapter of the book.
+    pub chapter: u32,
+    /// The number of the verse.
+    pub verse: u32,
+    /// The text of the verse.
+    pub text: String,
+}
+
+impl Verse {
+    /// Returns the reference of the verse, for example `John 3:16`.
+    pub fn reference(&self) -> String {
+        format!("{} {}:{}", self.book, self.chapter, self.verse)
+    }
+
+    /// Returns `true` when a chapter title such as `John 3:16` names this verse.
+    pub fn matches(&self, title: &str) -> bool {
+        match probe::split_title(title) {
+            Some((book, chapter, verse)) => {
+                book_key(book) == book_key(&self.book)
+                    && chapter == self.chapter
+                    && verse == self.verse
+            }
+            None => false,
+        }
+    }
+}
+
+/// The text of every verse in the corpus, in the order they were read.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Corpus {
+    verses: Vec<Verse>,
+    index: HashMap<(String, u32, u32), usize>,
+}
+
+impl Corpus {
+    /// Reads the corpus at `path`, choosing the layout from its extension. Files other than `.json` and `.csv` are read as tab separated lines.
+    pub fn read(path: &Path) -> Result<Corpus, Error> {
+        let file: String = fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Corpus::from_json(&file),
+            Some("csv") => Corpus::from_csv(&file),
+            _ => Corpus::from_lines(&file),
+        }
+    }
+
+    /// Parses a corpus in either of the JSON layouts.
+    pub fn from_json(file: &str) -> Result<Corpus, Error> {
+        let mut corpus = Corpus::default();
+
+        match serde_json::from_str::<Value>(file)? {
+            Value::Array(entries) => {
+                for (i, entry) in entries.iter().enumerate() {
+                    let number = |key: &str| match &entry[key] {
+                        Value::Number(n) => n.as_u64().map(|n| n as u32),
+                        Value::String(s) => s.trim().parse().ok(),
+                        _ => None,
+                    };
+                    match (
+                        entry["book"].as_str(),
+                        number("chapter"),
+                        number("verse"),
+                        entry["text"].as_str(),
+                    ) {
+                        (Some(book), Some(chapter), Some(verse), Some(text)) => {
+                            corpus.insert(book, chapter, verse, text)
+                        }
+                        _ => return Err(Error::Malformed { entry: i + 1 }),
+                    }
+                }
+            }
+            Value::Object(books) => {
+                let mut entry: usize = 0;
+                for (book, chapters) in books.iter() {
+                    for (chapter, verses) in chapters.as_object().into_iter().flatten() {
+                        for (verse, text) in verses.as_object().into_iter().flatten() {
+                            entry += 1;
+                            match (chapter.parse(), verse.parse(), text.as_str()) {
+                                (Ok(chapter), Ok(verse), Some(text)) => {
+                                    corpus.insert(book, chapter, verse, text)
+                                }
+                                _ => return Err(Error::Malformed { entry }),
+                            }
+                        }
+                    }
+                }
+            }
+            _ => return Err(Error::Malformed { entry: 1 }),
+        }
+
+        Ok(corpus)
+    }
+
+    /// Parses a corpus in the CSV layout. A first row whose chapter isn't a number is taken as the header.
+    pub fn from_csv(file: &str) -> Result<Corpus, Error> {
+        let mut corpus = Corpus::default();
+
+        for (i, line) in file.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<String> = csv_fields(line);
+            let row: Option<(&str, u32, u32, &str)> = match fields.as_slice() {
+                [book, chapter, verse, text] => chapter
+                    .trim()
+                    .parse()
+                    .ok()
+                    .zip(verse.trim().parse().ok())
+                    .map(|(c, v)| (book.as_str(), c, v, text.as_str())),
+                _ => None,
+            };
+
+            match row {
+                Some((book, chapter, verse, text)) => corpus.insert(book, chapter, verse, text),
+                None if i == 0 => (),
+                None => return Err(Error::Malformed { entry: i + 1 }),
+            }
+        }
+
+        Ok(corpus)
+    }
+
+    /// Parses a corpus of `reference<TAB>text` lines, where the reference is a title such as `John 3:16`.
+    pub fn from_lines(file: &str) -> Result<Corpus, Error> {
+        let mut corpus = Corpus::default();
+
+        for (i, line) in file.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (reference, text) = line
+                .split_once('\t')
+                .ok_or(Error::Malformed { entry: i + 1 })?;
+            match probe::split_title(reference.trim()) {
+                Some((book, chapter, verse)) => corpus.insert(book, chapter, verse, text),
+                None => return Err(Error::Malformed { entry: i + 1 }),
+            }
+        }
+
+        Ok(corpus)
+    }
+
+    /// Adds the verses of `other` to the corpus, in place of any it already has.
+    pub fn merge(&mut self, other: Corpus) {
+        for verse in other.verses {
+            self.insert(&verse.book, verse.chapter, verse.verse, &verse.text);
+        }
+    }
+
+    /// Returns the text of a verse.
+    pub fn verse(&self, book: &str, chapter: u32, verse: u32) -> Option<&str> {
+        self.index
+            .get(&(book_key(book), chapter, verse))
+            .map(|i| self.verses[*i].text.as_str())
+    }
+
+    /// Returns the text of the verse named by a chapter title such as `John 3:16`.
+    pub fn text(&self, title: &str) -> Option<&str> {
+        let (book, chapter, verse) = probe::split_title(title)?;
+        self.verse(book, chapter, verse)
+    }
+
+    /**
+    Returns the verses containing the `phrase`, in the order of the corpus.
+
+    The phrase is matched ignoring case, punctuation and how the words are spaced.
+    */
+    pub fn search(&self, phrase: &str) -> Vec<&Verse> {
+        let phrase: String = words(phrase);
+        if phrase.is_empty() {
+            return Vec::new();
+        }
+
+        self.verses
+            .iter()
+            .filter(|v| words(&v.text).contains(&phrase))
+            .collect()
+    }
+
+    fn insert(&mut self, book: &str, chapter: u32, verse: u32, text: &str) {
+        let verse = Verse {
+            book: book.trim().to_string(),
+            chapter,
+            verse,
+            text: text.trim().to_string(),
+        };
+        match self.index.get(&(book_key(book), chapter, verse.verse)) {
+            Some(i) => self.verses[*i] = verse,
+            None => {
+                self.index
+                    .insert((book_key(book), chapter, verse.verse), self.verses.len());
+                self.verses.push(verse);
+            }
+        }
+    }
+}
+
+/// Returns the words of the text in lower case, separated by single spaces.
+fn words(text: &str) -> String {
+    text.split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// Splits a CSV line into its fields, unquoting quoted fields and their doubled quotes.
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut quoted: bool = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_json_list() {
+        let corpus = Corpus::from_json(
+            r#"[{"book": "John", "chapter": 3, "verse": 16, "text": "For God loved the world so much"},
+                {"book": "1 John", "chapter": "4", "verse": "8", "text": "God is love."}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            corpus.text("John 3:16"),
+            Some("For God loved the world so much")
+        );
+        assert_eq!(corpus.verse("1john", 4, 8), Some("God is love."));
+    }
+
+    #[test]
+    fn test_from_json_nested() {
+        let corpus = Corpus::from_json(r#"{"Ps.": {"83": {"18": "Jehovah"}}}"#).unwrap();
+        assert_eq!(corpus.text("Ps 83:18"), Some("Jehovah"));
+        assert!(Corpus::from_json(r#"{"John": {"3": {"x": "text"}}}"#).is_err());
+    }
+
+    #[test]
+    fn test_search() {
+        let corpus = Corpus::from_csv(
+            "John,3,16,\"For God loved the world so much that he gave his only-begotten Son\"\n\
+             John,3,17,\"For God did not send his Son into the world to judge the world\"\n\
+             1 John,4,8,God is love.\n",
+        )
+        .unwrap();
+        let hits: Vec<String> = corpus
+            .search("the WORLD, so much")
+            .iter()
+            .map(|v| v.reference())
+            .collect();
+        assert_eq!(hits, vec!["John 3:16"]);
+        assert_eq!(corpus.search("Son").len(), 2);
+        assert!(corpus.search("  ").is_empty());
+        assert!(corpus.search("God is love")[0].matches("1 John 4:8"));
+    }
+
+    #[test]
+    fn test_from_csv() {
+        let corpus = Corpus::from_csv(
+            "book,chapter,verse,text\nJohn,3,16,\"For God loved the world so much, \"\"that\"\"\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            corpus.text("John 3:16"),
+            Some("For God loved the world so much, \"that\"")
+        );
+        assert!(matches!(
+            Corpus::from_csv("John,3,16,text\nJohn,3\n").unwrap_err(),
+            Error::Malformed { entry: 2 }
+        ));
+    }
+
+    #[test]
+    fn test_from_lines() {
+        let mut corpus = Corpus::from_lines(
+            "# Psalms\nPsalms 83:18\tThat people may know\nJohn 3:16\tFor God\n",
+        )
+        .unwrap();
+        assert_eq!(corpus.text("Ps. 83:18"), Some("That people may know"));
+        assert!(matches!(
+            Corpus::from_lines("John 3:16\tFor God\nJohn 3:17").unwrap_err(),
+            Error::Malformed { entry: 2 }
+        ));
+
+        corpus.merge(Corpus::from_lines("John 3:16\tFor God loved the world\n").unwrap());
+        assert_eq!(corpus.text("John 3:16"), Some("For God loved the world"));
+        assert_eq!(corpus.search("people").len(), 1);
+    }
+}
@@ -0,0 +1,242 @@
+/*!
+Title cards
+
+Synthesises a still card showing the reference, which is joined in front of an extracted clip.
+The card is generated at the resolution and frame rate of the source, with silent audio matching
+the source's, so it can be joined to the clip and encoded along with it.
+*/
+
+use crate::ffwrappers::drawtext::DrawText;
+use crate::ffwrappers::probe::Stream;
+use std::{path::Path, str::FromStr};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The title card background image {path:?} was not found")]
+    ImageNotFound { path: String },
+}
+
+/// The extensions of the images a background can be read from.
+const IMAGE_EXTENSIONS: [&str; 8] = ["png", "jpg", "jpeg", "bmp", "gif", "webp", "tif", "tiff"];
+
+/// What is drawn behind the text of a title card.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Background {
+    /// A solid color, any color ffmpeg understands such as `black` or `#1a2b3c`.
+    Color(String),
+    /// The path to an image, scaled to fit the card.
+    Image(String),
+}
+
+/**
+Reads a background as an image when it looks like a path, that is when it contains a path separator
+or ends in an image extension such as `.png`, and as a color otherwise.
+
+An image that doesn't exist is an error rather than being taken for a color.
+*/
+impl FromStr for Background {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let path: &Path = Path::new(s);
+        let image: bool = s.contains(['/', '\\'])
+            || path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+
+        match (image, path.is_file()) {
+            (false, _) => Ok(Background::Color(s.to_string())),
+            (true, true) => Ok(Background::Image(s.to_string())),
+            (true, false) => Err(Error::ImageNotFound {
+                path: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// A still card joined in front of a clip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TitleCard {
+    /// The text drawn on the card.
+    pub text: DrawText,
+    /// What is drawn behind the text.
+    pub background: Background,
+    /// How long the card is shown in seconds.
+    pub duration: f64,
+}
+
+/// The video and audio format of the source that a card has to match.
+#[derive(Debug, Clone, PartialEq)]
+struct SourceFormat {
+    width: u32,
+    height: u32,
+    frame_rate: String,
+    sample_rate: String,
+    channel_layout: String,
+}
+
+impl SourceFormat {
+    /// Returns the format of the first video and audio stream, falling back to 720p, 25 fps and 48 kHz stereo.
+    fn new(streams: &[Stream]) -> SourceFormat {
+        let video: Option<&Stream> = streams.iter().find(|s| s.codec_type == "video");
+        let audio: Option<&Stream> = streams.iter().find(|s| s.codec_type == "audio");
+        let or = |value: Option<&String>, default: &str| match value {
+            Some(v) if !v.is_empty() && v != "0/0" => v.clone(),
+            _ => default.to_string(),
+        };
+
+        SourceFormat {
+            width: video.and_then(|v| v.width).unwrap_or(1280),
+            height: video.and_then(|v| v.height).unwrap_or(720),
+            frame_rate: or(video.map(|v| &v.r_frame_rate), "25"),
+            sample_rate: or(audio.map(|a| &a.sample_rate), "48000"),
+            channel_layout: or(audio.map(|a| &a.channel_layout), "stereo"),
+        }
+    }
+}
+
+impl TitleCard {
+    /// Returns the arguments adding the background image as an input, empty for a solid color.
+    pub fn input_args(&self) -> Vec<String> {
+        match &self.background {
+            Background::Color(_) => Vec::new(),
+            Background::Image(image) => vec![
+                String::from("-loop"),
+                String::from("1"),
+                String::from("-t"),
+                self.duration.to_string(),
+                String::from("-i"),
+                image.clone(),
+            ],
+        }
+    }
+
+    /**
+    Returns the filter generating the card into the `[card_v]` and `[card_a]` pads.
+
+    The `input` is the index of the background image among ffmpeg's inputs, see [TitleCard::input_args].
+    */
+    pub fn filter(&self, input: usize, streams: &[Stream], no_audio: bool) -> String {
+        let source = SourceFormat::new(streams);
+        let size: String = format!("{}x{}", source.width, source.height);
+
+        let background: String = match &self.background {
+            Background::Color(color) => format!(
+                "color=c={}:s={}:r={}:d={}",
+                color, size, source.frame_rate, self.duration
+            ),
+            Background::Image(_) => format!(
+                "[{}:v]scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,fps={}",
+                input,
+                source.frame_rate,
+                w = source.width,
+                h = source.height
+            ),
+        };
+        let mut filter: String = format!(
+            "{},setsar=1,format=yuv420p,{}[card_v]",
+            background,
+            self.text.filter()
+        );
+
+        if !no_audio {
+            filter.push_str(&format!(
+                ";anullsrc=r={}:cl={},atrim=duration={}[card_a]",
+                source.sample_rate, source.channel_layout, self.duration
+            ));
+        }
+        filter
+    }
+
+    /// Returns the number of inputs the card adds to ffmpeg.
+    pub fn inputs(&self) -> usize {
+        match self.background {
+            Background::Color(_) => 0,
+            Background::Image(_) => 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn streams() -> Vec<Stream> {
+        vec![
+            Stream {
+                codec_type: String::from("video"),
+                width: Some(1920),
+                height: Some(1080),
+                r_frame_rate: String::from("30000/1001"),
+                ..Default::default()
+            },
+            Stream {
+                codec_type: String::from("audio"),
+                sample_rate: String::from("44100"),
+                channel_layout: String::from("mono"),
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn card(background: Background) -> TitleCard {
+        TitleCard {
+            text: DrawText {
+                text: String::from("John 3:16"),
+                ..Default::default()
+            },
+            background,
+            duration: 3.0,
+        }
+    }
+
+    #[test]
+    fn test_background_from_str() {
+        assert_eq!(
+            "#1a2b3c".parse::<Background>().unwrap(),
+            Background::Color(String::from("#1a2b3c"))
+        );
+        assert!(matches!(
+            "missing.png".parse::<Background>(),
+            Err(Error::ImageNotFound { .. })
+        ));
+        assert!(matches!(
+            "backgrounds/black".parse::<Background>(),
+            Err(Error::ImageNotFound { .. })
+        ));
+
+        let image = std::env::temp_dir().join(format!("scriptx-card-{}.png", std::process::id()));
+        std::fs::write(&image, b"").unwrap();
+        let path: String = image.to_string_lossy().into_owned();
+        assert_eq!(path.parse::<Background>().unwrap(), Background::Image(path));
+        std::fs::remove_file(image).unwrap();
+    }
+
+    #[test]
+    fn test_filter_color() {
+        let card = card(Background::Color(String::from("navy")));
+        assert!(card.input_args().is_empty());
+        assert_eq!(
+            card.filter(1, &streams(), false),
+            "color=c=navy:s=1920x1080:r=30000/1001:d=3,setsar=1,format=yuv420p,\
+             drawtext=text=John 3\\\\:16:expansion=none:fontsize=24:fontcolor=white:x=10:y=10[card_v];\
+             anullsrc=r=44100:cl=mono,atrim=duration=3[card_a]"
+        );
+    }
+
+    #[test]
+    fn test_filter_image() {
+        let card = card(Background::Image(String::from("card.png")));
+        assert_eq!(card.inputs(), 1);
+        assert_eq!(
+            card.input_args(),
+            vec!["-loop", "1", "-t", "3", "-i", "card.png"]
+        );
+        assert!(card
+            .filter(2, &[], true)
+            .starts_with("[2:v]scale=1280:720:force_original_aspect_ratio=decrease"));
+        assert!(!card.filter(2, &[], true).contains("anullsrc"));
+    }
+}
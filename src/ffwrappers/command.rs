@@ -0,0 +1,78 @@
+/*!
+Running ffmpeg and ffprobe
+
+Every call to ffmpeg or ffprobe goes through [run], which logs the command line, how long it took
+and how it exited. The tools are kept quiet unless debug messages are being logged, then their own
+log level is raised with [loglevel] and what they write to stderr is passed on to the log.
+*/
+
+use crate::ffwrappers::logging::{debug, info, trace};
+#[cfg(feature = "log")]
+use log::LevelFilter;
+use std::{
+    io,
+    process::{Command, Output},
+    time::Instant,
+};
+
+/// Returns the `-v` level to run ffmpeg and ffprobe at, following the level ScriptX logs at.
+#[cfg(feature = "log")]
+pub fn loglevel() -> &'static str {
+    loglevel_for(log::max_level())
+}
+
+/// Returns the `-v` level to run ffmpeg and ffprobe at, always `quiet` without the `log` feature.
+#[cfg(not(feature = "log"))]
+pub fn loglevel() -> &'static str {
+    "quiet"
+}
+
+/// Returns the ffmpeg log level matching `level`.
+#[cfg(feature = "log")]
+fn loglevel_for(level: LevelFilter) -> &'static str {
+    match level {
+        LevelFilter::Trace => "verbose",
+        LevelFilter::Debug => "info",
+        _ => "quiet",
+    }
+}
+
+/**
+Runs the `command` to completion, logging the command line, its exit status and how long it took.
+
+# Example
+```rust, ignore
+let output = command::run(Command::new("ffprobe").args(["-v", command::loglevel(), "-i", "John.mp4"]))?;
+```
+*/
+pub fn run(command: &mut Command) -> io::Result<Output> {
+    info!("Running {:?}", command);
+    let started: Instant = Instant::now();
+    let output: Output = command.output()?;
+
+    let program = command.get_program().to_string_lossy().into_owned();
+    info!(
+        "{} finished in {:.2?} with {}",
+        program,
+        started.elapsed(),
+        output.status
+    );
+    for line in String::from_utf8_lossy(&output.stderr).lines() {
+        debug!("{}: {}", program, line);
+    }
+    trace!("{} wrote {} bytes to stdout", program, output.stdout.len());
+    Ok(output)
+}
+
+#[cfg(all(test, feature = "log"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loglevel_for() {
+        assert_eq!(loglevel_for(LevelFilter::Off), "quiet");
+        assert_eq!(loglevel_for(LevelFilter::Info), "quiet");
+        assert_eq!(loglevel_for(LevelFilter::Debug), "info");
+        assert_eq!(loglevel_for(LevelFilter::Trace), "verbose");
+    }
+}
@@ -0,0 +1,198 @@
+/*!
+Text rendering with ffmpeg's [drawtext](https://ffmpeg.org/ffmpeg-filters.html#drawtext-1) filter
+
+Builds the `drawtext` filters used to label contact sheets and overlay references on clips.
+*/
+
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The placement `{placement:?}` is not supported")]
+    UnknownPlacement { placement: String },
+}
+
+/// Where on the frame the text is placed, kept a small margin away from the edges.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placement {
+    TopLeft,
+    Top,
+    TopRight,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+/// The names of the placements, as accepted on the command line.
+pub const PLACEMENTS: [&str; 6] = [
+    "top-left",
+    "top",
+    "top-right",
+    "bottom-left",
+    "bottom",
+    "bottom-right",
+];
+
+impl Placement {
+    /// Returns the `x` and `y` expressions placing the text.
+    pub fn position(&self) -> (String, String) {
+        let x: &str = match self {
+            Placement::TopLeft | Placement::BottomLeft => "20",
+            Placement::Top | Placement::Bottom => "(w-text_w)/2",
+            Placement::TopRight | Placement::BottomRight => "w-text_w-20",
+        };
+        let y: &str = match self {
+            Placement::TopLeft | Placement::Top | Placement::TopRight => "20",
+            _ => "h-text_h-20",
+        };
+        (x.to_string(), y.to_string())
+    }
+}
+
+impl FromStr for Placement {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "top-left" => Ok(Placement::TopLeft),
+            "top" => Ok(Placement::Top),
+            "top-right" => Ok(Placement::TopRight),
+            "bottom-left" => Ok(Placement::BottomLeft),
+            "bottom" => Ok(Placement::Bottom),
+            "bottom-right" => Ok(Placement::BottomRight),
+            _ => Err(Error::UnknownPlacement {
+                placement: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// How and where a piece of text is drawn onto the video.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawText {
+    /// The text drawn as it is, escaped by [DrawText::filter].
+    pub text: String,
+    /// The path to a font file. `None` uses ffmpeg's default font.
+    pub font: Option<String>,
+    /// The font size in pixels.
+    pub size: u32,
+    /// The color of the text, any color ffmpeg understands such as `white` or `#ffcc00`.
+    pub color: String,
+    /// The horizontal position, an ffmpeg expression such as `(w-text_w)/2`.
+    pub x: String,
+    /// The vertical position, an ffmpeg expression such as `h-text_h-10`.
+    pub y: String,
+    /// The color of the box drawn behind the text. `None` draws no box.
+    pub box_color: Option<String>,
+    /// An ffmpeg expression enabling the text only for some frames, such as `lt(t,3)`.
+    pub enable: Option<String>,
+}
+
+impl Default for DrawText {
+    fn default() -> Self {
+        DrawText {
+            text: String::new(),
+            font: None,
+            size: 24,
+            color: String::from("white"),
+            x: String::from("10"),
+            y: String::from("10"),
+            box_color: None,
+            enable: None,
+        }
+    }
+}
+
+impl DrawText {
+    /// Returns the `drawtext` filter drawing the text.
+    pub fn filter(&self) -> String {
+        let mut options: Vec<String> = vec![
+            format!("text={}", escape(&self.text)),
+            String::from("expansion=none"),
+        ];
+
+        if let Some(font) = &self.font {
+            options.push(format!("fontfile={}", escape(font)));
+        }
+        options.push(format!("fontsize={}", self.size));
+        options.push(format!("fontcolor={}", self.color));
+        options.push(format!("x={}", self.x));
+        options.push(format!("y={}", self.y));
+        if let Some(color) = &self.box_color {
+            options.push(String::from("box=1"));
+            options.push(format!("boxcolor={}", color));
+            options.push(String::from("boxborderw=8"));
+        }
+        if let Some(enable) = &self.enable {
+            options.push(format!("enable='{}'", enable));
+        }
+
+        format!("drawtext={}", options.join(":"))
+    }
+}
+
+/**
+Escapes text so it can be used as the value of a `drawtext` option in a filter graph.
+
+ffmpeg unescapes the value twice: first when it parses the filter graph, then when it parses the
+options of the filter. The value is escaped for the options first and for the filter graph second.
+The filter turns drawtext's own `%{...}` expansion off, so `%` needs no escaping.
+*/
+pub fn escape(text: &str) -> String {
+    let option: String = escape_chars(text, &['\\', '\'', ':']);
+    escape_chars(&option, &['\\', '\'', '[', ']', ',', ';'])
+}
+
+/// Returns the text with a backslash put before each of the `special` characters.
+fn escape_chars(text: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("John 3:16"), r"John 3\\:16");
+        // The filter graph leaves `God\'s love`, which the options parse as `God's love`.
+        assert_eq!(escape("God's love"), r"God\\\'s love");
+        assert_eq!(escape("100%"), "100%");
+        assert_eq!(escape(r"C:\Fonts\a,b.ttf"), r"C\\:\\\\Fonts\\\\a\,b.ttf");
+    }
+
+    #[test]
+    fn test_placement() {
+        let placement: Placement = "bottom-right".parse().unwrap();
+        assert_eq!(
+            placement.position(),
+            (String::from("w-text_w-20"), String::from("h-text_h-20"))
+        );
+        for name in PLACEMENTS.iter() {
+            assert!(name.parse::<Placement>().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let text = DrawText {
+            text: String::from("Ps. 83:18"),
+            box_color: Some(String::from("black@0.5")),
+            enable: Some(String::from("lt(t,3)")),
+            ..Default::default()
+        };
+        assert_eq!(
+            text.filter(),
+            "drawtext=text=Ps. 83\\\\:18:expansion=none:fontsize=24:fontcolor=white:x=10:y=10:\
+             box=1:boxcolor=black@0.5:boxborderw=8:enable='lt(t,3)'"
+        );
+    }
+}
@@ -0,0 +1,305 @@
+/*!
+Output containers and transcode presets
+
+This module knows which codecs each supported container can hold, so an incompatible combination
+is reported before ffmpeg starts cutting, and which encoders the named presets use.
+*/
+
+use crate::ffwrappers::probe::Stream;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The format `{format:?}` is not supported")]
+    UnknownFormat { format: String },
+    #[error("The preset `{preset:?}` does not exist")]
+    UnknownPreset { preset: String },
+    #[error("The {codec} stream can't be stored in a {container} file")]
+    Incompatible { codec: String, container: String },
+}
+
+/// The containers ScriptX can write.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Container {
+    Mp4,
+    Mkv,
+    Mov,
+    Webm,
+}
+
+impl Container {
+    /// Returns the file extension used by the container.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mkv => "mkv",
+            Container::Mov => "mov",
+            Container::Webm => "webm",
+        }
+    }
+
+    /// Returns the name of the ffmpeg muxer for the container, passed with `-f`.
+    pub fn muxer(&self) -> &'static str {
+        match self {
+            Container::Mp4 => "mp4",
+            Container::Mkv => "matroska",
+            Container::Mov => "mov",
+            Container::Webm => "webm",
+        }
+    }
+
+    /// Returns `true` if the container can hold a stream encoded with `codec`.
+    pub fn supports(&self, codec: &str) -> bool {
+        match self {
+            Container::Mkv => true,
+            Container::Mp4 => matches!(
+                codec,
+                "h264" | "hevc" | "mpeg4" | "av1" | "vp9" | "aac" | "mp3" | "ac3" | "opus" | "alac"
+            ),
+            Container::Mov => matches!(
+                codec,
+                "h264"
+                    | "hevc"
+                    | "mpeg4"
+                    | "prores"
+                    | "mjpeg"
+                    | "aac"
+                    | "mp3"
+                    | "alac"
+                    | "pcm_s16le"
+            ),
+            Container::Webm => matches!(codec, "vp8" | "vp9" | "av1" | "vorbis" | "opus"),
+        }
+    }
+
+    /// Returns the codec soft subtitles are written with in the container.
+    pub fn subtitle_codec(&self) -> &'static str {
+        match self {
+            Container::Mp4 | Container::Mov => "mov_text",
+            Container::Mkv => "srt",
+            Container::Webm => "webvtt",
+        }
+    }
+
+    /**
+    Checks that every video and audio stream of the source can be copied into the container.
+
+    Other kinds of streams are not selected by ffmpeg when cutting, so they are not checked.
+    */
+    pub fn check_streams(&self, streams: &[Stream]) -> Result<(), Error> {
+        for stream in streams {
+            if stream.codec_type == "video" || stream.codec_type == "audio" {
+                self.check_codec(&stream.codec_name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the container can't hold a stream encoded with `codec`.
+    pub fn check_codec(&self, codec: &str) -> Result<(), Error> {
+        match self.supports(codec) {
+            true => Ok(()),
+            false => Err(Error::Incompatible {
+                codec: codec.to_string(),
+                container: self.extension().to_string(),
+            }),
+        }
+    }
+}
+
+impl FromStr for Container {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mp4" | "m4v" => Ok(Container::Mp4),
+            "mkv" => Ok(Container::Mkv),
+            "mov" => Ok(Container::Mov),
+            "webm" => Ok(Container::Webm),
+            _ => Err(Error::UnknownFormat {
+                format: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// The names of the presets, in the order shown in `--help`.
+pub const PRESETS: [&str; 3] = ["phone-480p", "projector-1080p", "archive"];
+
+/// The codecs a re-encoded output can be written with, see [encoder].
+const ENCODABLE: [&str; 7] = ["h264", "hevc", "vp9", "aac", "opus", "vorbis", "mp3"];
+
+/// The video codecs among [ENCODABLE].
+const VIDEO_CODECS: [&str; 3] = ["h264", "hevc", "vp9"];
+
+/**
+A named set of encoder settings used when transcoding a cut.
+
+Codecs are given by their codec name, for example `h264`, so they can be checked against the container.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Preset {
+    /// The codec of the video stream.
+    pub video_codec: &'static str,
+    /// The bitrate of the video stream, for example `1M`.
+    pub video_bitrate: &'static str,
+    /// The codec of the audio stream.
+    pub audio_codec: &'static str,
+    /// The bitrate of the audio stream, for example `128k`.
+    pub audio_bitrate: &'static str,
+    /// The height the video is scaled to, keeping its aspect ratio. `None` keeps the source height.
+    pub height: Option<u32>,
+}
+
+impl Preset {
+    /// Returns the encoder settings used when the output has to be re-encoded but no preset was chosen.
+    pub fn default_for(container: Option<Container>) -> Preset {
+        let (video_codec, audio_codec) = match container {
+            Some(Container::Webm) => ("vp9", "opus"),
+            _ => ("h264", "aac"),
+        };
+        Preset {
+            video_codec,
+            video_bitrate: "",
+            audio_codec,
+            audio_bitrate: "",
+            height: None,
+        }
+    }
+
+    /**
+    Returns the encoder settings matching the codecs of the source `streams`.
+
+    Falls back to [Preset::default_for] for any codec that can't be encoded or doesn't fit the container.
+    */
+    pub fn matching(container: Option<Container>, streams: &[Stream]) -> Preset {
+        let mut preset: Preset = Preset::default_for(container);
+        let fits = |codec: &str| container.map_or(true, |c| c.supports(codec));
+
+        for stream in streams {
+            let codec: Option<&'static str> =
+                ENCODABLE.iter().find(|c| **c == stream.codec_name).copied();
+            match (stream.codec_type.as_str(), codec) {
+                ("video", Some(c)) if VIDEO_CODECS.contains(&c) && fits(c) => {
+                    preset.video_codec = c
+                }
+                ("audio", Some(c)) if !VIDEO_CODECS.contains(&c) && fits(c) => {
+                    preset.audio_codec = c
+                }
+                _ => (),
+            }
+        }
+        preset
+    }
+
+    /// Checks that the container can hold both codecs of the preset.
+    pub fn check(&self, container: Container) -> Result<(), Error> {
+        container.check_codec(self.video_codec)?;
+        container.check_codec(self.audio_codec)
+    }
+}
+
+impl FromStr for Preset {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "phone-480p" => Ok(Preset {
+                video_codec: "h264",
+                video_bitrate: "1M",
+                audio_codec: "aac",
+                audio_bitrate: "96k",
+                height: Some(480),
+            }),
+            "projector-1080p" => Ok(Preset {
+                video_codec: "h264",
+                video_bitrate: "6M",
+                audio_codec: "aac",
+                audio_bitrate: "192k",
+                height: Some(1080),
+            }),
+            "archive" => Ok(Preset {
+                video_codec: "hevc",
+                video_bitrate: "12M",
+                audio_codec: "aac",
+                audio_bitrate: "256k",
+                height: None,
+            }),
+            _ => Err(Error::UnknownPreset {
+                preset: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Returns the ffmpeg encoder used to produce `codec`.
+pub fn encoder(codec: &str) -> &str {
+    match codec {
+        "h264" => "libx264",
+        "hevc" => "libx265",
+        "vp9" => "libvpx-vp9",
+        "opus" => "libopus",
+        "vorbis" => "libvorbis",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(codec_type: &str, codec_name: &str) -> Stream {
+        Stream {
+            index: 0,
+            codec_type: String::from(codec_type),
+            codec_name: String::from(codec_name),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_container_from_str() {
+        assert_eq!("mkv".parse::<Container>().unwrap(), Container::Mkv);
+        assert_eq!("m4v".parse::<Container>().unwrap(), Container::Mp4);
+        assert!("avi".parse::<Container>().is_err());
+    }
+
+    #[test]
+    fn test_check_streams() {
+        let streams = vec![
+            stream("video", "h264"),
+            stream("audio", "aac"),
+            stream("data", "bin_data"),
+        ];
+        assert!(Container::Mp4.check_streams(&streams).is_ok());
+        assert!(Container::Mkv.check_streams(&streams).is_ok());
+        assert!(Container::Webm.check_streams(&streams).is_err());
+    }
+
+    #[test]
+    fn test_preset_check() {
+        let preset: Preset = "phone-480p".parse().unwrap();
+        assert!(preset.check(Container::Mp4).is_ok());
+        assert!(preset.check(Container::Webm).is_err());
+        assert!(Preset::default_for(Some(Container::Webm))
+            .check(Container::Webm)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_preset_matching() {
+        let streams = vec![stream("video", "hevc"), stream("audio", "pcm_s16le")];
+        let preset = Preset::matching(Some(Container::Mp4), &streams);
+        assert_eq!((preset.video_codec, preset.audio_codec), ("hevc", "aac"));
+        let preset = Preset::matching(Some(Container::Webm), &streams);
+        assert_eq!((preset.video_codec, preset.audio_codec), ("vp9", "opus"));
+    }
+
+    #[test]
+    fn test_presets_parse() {
+        for name in PRESETS.iter() {
+            assert!(name.parse::<Preset>().is_ok());
+        }
+    }
+}
@@ -0,0 +1,34 @@
+/*!
+Logging
+
+The wrappers log through the [log](https://docs.rs/log) crate when the `log` feature is enabled, as it
+is for the command line tool. Without it the same macros discard their messages, so the library
+builds without `log`.
+*/
+
+#[cfg(feature = "log")]
+pub(crate) use log::{debug, info, trace};
+
+#[cfg(not(feature = "log"))]
+macro_rules! debug {
+    ($($arg:tt)+) => {{
+        let _ = format_args!($($arg)+);
+    }};
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! info {
+    ($($arg:tt)+) => {{
+        let _ = format_args!($($arg)+);
+    }};
+}
+
+#[cfg(not(feature = "log"))]
+macro_rules! trace {
+    ($($arg:tt)+) => {{
+        let _ = format_args!($($arg)+);
+    }};
+}
+
+#[cfg(not(feature = "log"))]
+pub(crate) use {debug, info, trace};
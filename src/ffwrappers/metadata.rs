@@ -0,0 +1,391 @@
+/*!
+Chapter metadata and descriptive tags for extracted clips
+
+A clip would otherwise carry every chapter of the source file with the source's timestamps. This
+module works out which chapters fall inside a cut, rebases their times to the start of the clip,
+and writes them as an [FFMETADATA](https://ffmpeg.org/ffmpeg-formats.html#Metadata-1) file that
+ffmpeg muxes into the output.
+
+It also renders the descriptive tags, such as the title and album, written to each clip.
+*/
+
+use crate::ffwrappers::probe::{self, Root};
+use crate::timestamp::Timestamp;
+use regex::Regex;
+use std::{fs, io, path::Path};
+
+/**
+The tags written to every clip unless overridden, as `(key, template)` pairs.
+
+See [TagContext] for the placeholders a template can use.
+*/
+pub const DEFAULT_TAGS: [(&str, &str); 5] = [
+    ("title", "{reference}"),
+    ("album", "{book}"),
+    ("track", "{verse}"),
+    ("comment", "Cut from {file} at {times}"),
+    ("language", "{language}"),
+];
+
+/**
+The values a tag template can refer to.
+
+Each field is available as a placeholder of the same name, for example `{reference}`.
+*/
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct TagContext {
+    /// The scripture reference of the clip, for example `John 3:16-18`.
+    pub reference: String,
+    /// The book of the clip, for example `John`.
+    pub book: String,
+    /// The chapter of the book.
+    pub chapter: String,
+    /// The first verse of the clip.
+    pub verse: String,
+    /// The file name of the source video.
+    pub file: String,
+    /// The *start* and *end* times of each segment within the source, for example `197.597-241.908`.
+    pub times: String,
+    /// The language code of the clip, for example `ASL`.
+    pub language: String,
+}
+
+impl TagContext {
+    /**
+    Returns the context describing a clip made of the `chapters` cut out of `source` at the *segments*.
+
+    The reference is built from the titles of the chapters, so chapters which don't follow the
+    "Book C:V" pattern are left out of it.
+    */
+    pub fn new(
+        chapters: &[ClipChapter],
+        source: &Path,
+        segments: &[(Timestamp, Timestamp)],
+        language: &str,
+    ) -> TagContext {
+        let titles: Vec<(&str, u32, u32)> = chapters
+            .iter()
+            .filter_map(|c| probe::split_title(&c.title))
+            .collect();
+        let verses: Vec<u32> = titles.iter().map(|t| t.2).collect();
+
+        let mut context = TagContext {
+            file: source
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            times: segments
+                .iter()
+                .map(|(s, e)| format!("{}-{}", s, e))
+                .collect::<Vec<String>>()
+                .join(", "),
+            language: language.to_string(),
+            ..Default::default()
+        };
+
+        if let Some((book, chapter, verse)) = titles.first() {
+            context.reference = format!("{} {}:{}", book, chapter, verse_list(&verses));
+            context.book = book.to_string();
+            context.chapter = chapter.to_string();
+            context.verse = verse.to_string();
+        }
+
+        context
+    }
+
+    /// Returns the template with every placeholder replaced by its value.
+    pub fn render(&self, template: &str) -> String {
+        template
+            .replace("{reference}", &self.reference)
+            .replace("{book}", &self.book)
+            .replace("{chapter}", &self.chapter)
+            .replace("{verse}", &self.verse)
+            .replace("{file}", &self.file)
+            .replace("{times}", &self.times)
+            .replace("{language}", &self.language)
+    }
+}
+
+/**
+Returns the tags for a clip by rendering each template against the `context`.
+
+The `templates` override the [DEFAULT_TAGS] with the same key and add any other keys. A template
+rendering to an empty or blank value leaves the tag out, so `--tag comment=` removes the comment.
+*/
+pub fn clip_tags(context: &TagContext, templates: &[(String, String)]) -> Vec<(String, String)> {
+    let mut tags: Vec<(String, String)> = DEFAULT_TAGS
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+    for (key, template) in templates {
+        match tags.iter_mut().find(|(k, _)| k == key) {
+            Some(tag) => tag.1 = template.clone(),
+            None => tags.push((key.clone(), template.clone())),
+        }
+    }
+
+    tags.into_iter()
+        .map(|(key, template)| (key, context.render(&template)))
+        .filter(|(_, value)| !value.trim().is_empty())
+        .collect()
+}
+
+/**
+Returns the language code found in the name of an NWT file, for example `ASL` in
+`nwt_43_Joh_ASL_03_r720P.mp4`.
+*/
+pub fn language_from_file_name(path: &Path) -> Option<String> {
+    let name: &str = path.file_name()?.to_str()?;
+    let pattern = Regex::new(r"^nwt_\d+_[^_]+_([A-Za-z]+)_").unwrap();
+
+    Some(pattern.captures(name)?.get(1)?.as_str().to_string())
+}
+
+/// Returns the verses as a compact list, for example `3, 5-7` for the verses 3, 5, 6 and 7.
+fn verse_list(verses: &[u32]) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+
+    for verse in verses {
+        match ranges.last_mut() {
+            Some(range) if *verse == range.1 + 1 => range.1 = *verse,
+            Some(range) if *verse == range.1 => (),
+            _ => ranges.push((*verse, *verse)),
+        }
+    }
+
+    ranges
+        .iter()
+        .map(|(first, last)| match first == last {
+            true => first.to_string(),
+            false => format!("{}-{}", first, last),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// A chapter of an extracted clip, with times from the start of the clip.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct ClipChapter {
+    /// The start of the chapter within the clip.
+    pub start: Timestamp,
+    /// The end of the chapter within the clip.
+    pub end: Timestamp,
+    /// The title of the chapter, taken from the source chapter.
+    pub title: String,
+}
+
+/**
+Returns the chapters of `root` that fall inside the *segments*, rebased to the start of the clip.
+
+Segments are joined in order, overlapping by `crossfade` seconds. A chapter is kept when at least
+half of it lies inside a segment, so the sliver of a neighbouring verse added by padding doesn't
+show up as a chapter of its own.
+
+## Example
+
+```rust, ignore
+let segments = [("197.597".parse()?, "241.908".parse()?)];
+let chapters = metadata::clip_chapters(&root, &segments, 0.0);
+assert_eq!(chapters[1].start.to_string(), "28.662"); // John 3:17 starts 28.662 seconds into the clip.
+```
+*/
+pub fn clip_chapters(
+    root: &Root,
+    segments: &[(Timestamp, Timestamp)],
+    crossfade: f64,
+) -> Vec<ClipChapter> {
+    let mut chapters: Vec<ClipChapter> = Vec::new();
+    let crossfade: Timestamp = Timestamp::from_seconds(crossfade);
+    let mut offset: Timestamp = Timestamp::ZERO;
+
+    for (start_time, end_time) in segments {
+        for chapter in root.chapters.iter() {
+            let (chapter_start, chapter_end) = chapter.times();
+            let start: Timestamp = chapter_start.max(*start_time);
+            let end: Timestamp = chapter_end.min(*end_time);
+            let length: Timestamp = end - start;
+
+            if length > Timestamp::ZERO && length + length >= chapter_end - chapter_start {
+                chapters.push(ClipChapter {
+                    start: start - *start_time + offset,
+                    end: end - *start_time + offset,
+                    title: chapter.tags.title.clone(),
+                });
+            }
+        }
+        offset = offset + *end_time - *start_time - crossfade;
+    }
+
+    chapters
+}
+
+/// Moves the chapters `offset` later into the clip, making room for whatever is joined in front of them.
+pub fn shift_chapters(chapters: &mut [ClipChapter], offset: Timestamp) {
+    for chapter in chapters.iter_mut() {
+        chapter.start = chapter.start + offset;
+        chapter.end = chapter.end + offset;
+    }
+}
+
+/// Returns the chapters formatted as an FFMETADATA file, using a millisecond time base.
+pub fn ffmetadata(chapters: &[ClipChapter]) -> String {
+    let mut file = String::from(";FFMETADATA1\n");
+
+    for chapter in chapters {
+        file.push_str("\n[CHAPTER]\nTIMEBASE=1/1000\n");
+        file.push_str(&format!(
+            "START={}\n",
+            chapter.start.to_ticks("1/1000").unwrap_or_default()
+        ));
+        file.push_str(&format!(
+            "END={}\n",
+            chapter.end.to_ticks("1/1000").unwrap_or_default()
+        ));
+        file.push_str(&format!("title={}\n", escape(&chapter.title)));
+    }
+
+    file
+}
+
+/// Writes the chapters as an FFMETADATA file to `path`.
+pub fn write_ffmetadata(chapters: &[ClipChapter], path: &Path) -> Result<(), io::Error> {
+    fs::write(path, ffmetadata(chapters))
+}
+
+/// Escapes the characters that have a special meaning in an FFMETADATA file.
+pub fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffwrappers::probe::fixtures::init_struct_1;
+    use crate::timestamp::t;
+
+    fn span(start: &str, end: &str) -> (Timestamp, Timestamp) {
+        (t(start), t(end))
+    }
+
+    fn round(chapters: Vec<ClipChapter>) -> Vec<(i64, i64, String)> {
+        chapters
+            .into_iter()
+            .map(|c| {
+                (
+                    c.start.to_ticks("1/1000").unwrap(),
+                    c.end.to_ticks("1/1000").unwrap(),
+                    c.title,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_clip_chapters() {
+        let chapters = clip_chapters(&init_struct_1(), &[span("197.597", "241.908")], 0.0);
+        assert_eq!(
+            round(chapters),
+            vec![
+                (0, 28662, String::from("John 3:16")),
+                (28662, 44311, String::from("John 3:17")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clip_chapters_padded() {
+        let chapters = clip_chapters(&init_struct_1(), &[span("197.097", "226.259")], 0.0);
+        assert_eq!(
+            round(chapters),
+            vec![(500, 29162, String::from("John 3:16"))]
+        );
+    }
+
+    #[test]
+    fn test_clip_chapters_joined() {
+        let chapters = clip_chapters(
+            &init_struct_1(),
+            &[span("226.259", "241.908"), span("358.658", "374.741")],
+            1.0,
+        );
+        assert_eq!(
+            round(chapters),
+            vec![
+                (0, 15649, String::from("John 3:17")),
+                (14649, 30732, String::from("John 3:25")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shift_chapters() {
+        let mut chapters = clip_chapters(&init_struct_1(), &[span("197.597", "241.908")], 0.0);
+        shift_chapters(&mut chapters, t("2.5"));
+        assert_eq!(
+            round(chapters)[1],
+            (31162, 46811, String::from("John 3:17"))
+        );
+    }
+
+    #[test]
+    fn test_verse_list() {
+        assert_eq!(verse_list(&[16]), "16");
+        assert_eq!(verse_list(&[16, 17, 18]), "16-18");
+        assert_eq!(verse_list(&[3, 5, 6, 7, 7]), "3, 5-7");
+    }
+
+    #[test]
+    fn test_clip_tags() {
+        let chapters = clip_chapters(&init_struct_1(), &[span("197.597", "241.908")], 0.0);
+        let context = TagContext::new(
+            &chapters,
+            Path::new("videos/nwt_43_Joh_ASL_03_r720P.mp4"),
+            &[span("197.597", "241.908")],
+            "ASL",
+        );
+        let templates = vec![
+            (String::from("comment"), String::new()),
+            (String::from("artist"), String::from("{book} {chapter}")),
+        ];
+        assert_eq!(
+            clip_tags(&context, &templates),
+            vec![
+                (String::from("title"), String::from("John 3:16-17")),
+                (String::from("album"), String::from("John")),
+                (String::from("track"), String::from("16")),
+                (String::from("language"), String::from("ASL")),
+                (String::from("artist"), String::from("John 3")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_language_from_file_name() {
+        assert_eq!(
+            language_from_file_name(Path::new("nwt_43_Joh_ASL_03_r720P.mp4")),
+            Some(String::from("ASL"))
+        );
+        assert_eq!(language_from_file_name(Path::new("output.m4v")), None);
+    }
+
+    #[test]
+    fn test_ffmetadata() {
+        let chapters = vec![ClipChapter {
+            start: t("0"),
+            end: t("28.662"),
+            title: String::from("John 3:16; note"),
+        }];
+        assert_eq!(
+            ffmetadata(&chapters),
+            ";FFMETADATA1\n\n[CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=28662\ntitle=John 3:16\\; note\n"
+        );
+    }
+}
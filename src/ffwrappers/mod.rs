@@ -3,6 +3,15 @@
 These two tools must be installed on the system in order for ScriptX to work.
 */
 
+pub mod card;
+pub mod command;
+pub mod drawtext;
 pub mod errors;
+pub mod format;
+mod logging;
+pub mod metadata;
 pub mod mpeg;
+pub mod preview;
 pub mod probe;
+pub mod sheet;
+pub mod thumbnail;
@@ -1,9 +1,156 @@
-/**
+/*!
 ffmpeg wrapper
 
 A simple wrapper used to slice specific verses out of video files.
 
+Cuts are stream copied whenever possible. When fades, crossfades or a transcode preset are requested
+the cut has to be re-encoded, in which case the segments are trimmed and joined with ffmpeg's filters instead.
+
+Times are passed as exact [Timestamp]s and written to ffmpeg's arguments without going through floating point.
+
+The chapters of the clip are muxed in from a temporary FFMETADATA file so they match the clip rather than the source.
+Soft subtitles are muxed in the same way from a temporary SubRip file.
+*/
+
+use crate::ffwrappers::card::TitleCard;
+use crate::ffwrappers::command;
+use crate::ffwrappers::drawtext::DrawText;
+use crate::ffwrappers::format::{self, Container, Preset};
+use crate::ffwrappers::logging::debug;
+use crate::ffwrappers::metadata::{self, ClipChapter};
+use crate::ffwrappers::probe::{Root, Stream};
+use crate::subtitles::{self, Cue, SubtitleFormat};
+use crate::timestamp::Timestamp;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("ffmpeg's exit status was FAILURE")]
+    Failure,
+    #[error("Unable to run ffmpeg: {0}")]
+    Run(#[source] std::io::Error),
+    #[error("The crossfade of {crossfade}s is not shorter than a {segment}s segment")]
+    CrossfadeTooLong {
+        crossfade: Timestamp,
+        segment: Timestamp,
+    },
+    #[error("Unable to write the chapter metadata or subtitles: {0}")]
+    Metadata(#[from] std::io::Error),
+    #[error("The timestamp mode `{mode:?}` is not supported")]
+    UnknownTimestamps { mode: String },
+    #[error("Unable to probe the output {output:?}")]
+    Probe { output: String },
+    #[error("The output {output:?} starts at {start}s instead of zero")]
+    NonZeroStart { output: String, start: Timestamp },
+}
+
+/// How far from zero, in seconds, a reset output may start. Covers the priming samples of the audio encoder.
+const START_TOLERANCE: f64 = 0.05;
+
+/**
+How the timestamps of the source are carried into the output.
+
+`Preserve` keeps the source's timestamps, so a clip cut from the middle of a chapter file starts at a
+non-zero timestamp. Its chapters and subtitles are rebased to that timestamp, the start of the clip,
+so they stay in step with the video. `Reset` rebases the clip, its chapters and its subtitles to start
+at zero, which some editors and players need.
+*/
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub enum Timestamps {
+    #[default]
+    Preserve,
+    Reset,
+}
+
+impl FromStr for Timestamps {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "preserve" => Ok(Timestamps::Preserve),
+            "reset" => Ok(Timestamps::Reset),
+            _ => Err(Error::UnknownTimestamps {
+                mode: s.to_string(),
+            }),
+        }
+    }
+}
+
+/**
+Options controlling how a cut is written.
+
+The default options stream copy the cut without touching it.
 */
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct CutOptions {
+    /// Length in seconds of the fade in at the start of the output.
+    pub fade_in: f64,
+    /// Length in seconds of the fade out at the end of the output.
+    pub fade_out: f64,
+    /// Length in seconds of the crossfade between joined segments.
+    pub crossfade: f64,
+    /// Set when the source has no audio stream, so no audio filters are added.
+    pub no_audio: bool,
+    /// The container of the output. `None` lets ffmpeg guess it from the output's extension.
+    pub container: Option<Container>,
+    /// The transcode preset, setting one always re-encodes the output.
+    pub preset: Option<Preset>,
+    /// The chapters written to the output, with times relative to the start of the clip.
+    pub chapters: Vec<ClipChapter>,
+    /// How the timestamps of the source are carried into the output.
+    pub timestamps: Timestamps,
+    /// The descriptive tags written to the output, as `(key, value)` pairs.
+    pub tags: Vec<(String, String)>,
+    /// The text burnt into the video, setting one always re-encodes the output.
+    pub label: Option<DrawText>,
+    /// The card joined in front of the clip, setting one always re-encodes the output.
+    pub title_card: Option<TitleCard>,
+    /// The streams of the source, re-encoded outputs keep their codecs where the container allows.
+    pub streams: Vec<Stream>,
+    /// The soft subtitles muxed into the output, with times relative to the start of the clip.
+    pub subtitles: Vec<Cue>,
+}
+
+impl CutOptions {
+    /// Returns `true` when the options can't be applied with a stream copy.
+    pub fn needs_encoding(&self) -> bool {
+        self.fade_in > 0.0
+            || self.fade_out > 0.0
+            || self.preset.is_some()
+            || self.label.is_some()
+            || self.title_card.is_some()
+    }
+
+    /// Returns the encoder settings used when the output is re-encoded.
+    fn encoding(&self) -> Preset {
+        self.preset
+            .unwrap_or_else(|| Preset::matching(self.container, &self.streams))
+    }
+
+    /**
+    Checks that the output container can hold the cut, before any cutting starts.
+
+    When the output is stream copied the codecs of the source `streams` are checked, otherwise the
+    codecs of the encoder settings are.
+    */
+    pub fn check(&self, streams: &[Stream], joined: bool) -> Result<(), format::Error> {
+        let container: Container = match self.container {
+            Some(c) => c,
+            None => return Ok(()),
+        };
+
+        match joined || self.needs_encoding() {
+            true => self.encoding().check(container),
+            false => container.check_streams(streams),
+        }
+    }
+}
 
 /**
 Cuts the video out at specific *start* and *end* times.
@@ -11,35 +158,895 @@ Cuts the video out at specific *start* and *end* times.
 # Example
 ```rust, ignore
 use ff:mpeg;
-mpeg::cut(23.32342, 50.234234, "nwt_43_Joh_ASL_03_r720P.mp4", "John_3-5.mp4"); // Slices the given video at the given time stamp and outputs to *John_3-5.mp4*.
+mpeg::cut("23.32342".parse()?, "50.234234".parse()?, "nwt_43_Joh_ASL_03_r720P.mp4", "John_3-5.mp4", &CutOptions::default()); // Slices the given video at the given time stamp and outputs to *John_3-5.mp4*.
 ```
 */
-use std::{path::Path, process::Command};
-
-pub fn cut(start_time: f64, end_time: f64, path: &Path, output: &str) {
-    dbg!("{}", start_time);
-    dbg!("{}", end_time);
-
-    let command = Command::new("ffmpeg")
-        .arg("-v")
-        .arg("quiet")
-        .arg("-ss")
-        .arg(start_time.to_string())
-        .arg("-i")
-        .arg(path)
-        .arg("-to")
-        .arg(end_time.to_string())
-        .arg("-c")
-        .arg("copy")
-        .arg("-copyts")
-        .arg("-y")
-        .arg("-avoid_negative_ts")
-        .arg("1")
-        .arg(output)
-        .output()
-        .unwrap();
+pub fn cut(
+    start_time: Timestamp,
+    end_time: Timestamp,
+    path: &Path,
+    output: &str,
+    options: &CutOptions,
+) -> Result<(), Error> {
+    debug!(
+        "Cutting {} from {}s to {}s",
+        path.display(),
+        start_time,
+        end_time
+    );
+
+    if options.needs_encoding() {
+        return join(&[(start_time, end_time)], path, output, options);
+    }
+
+    // Preserved timestamps start at the cut, so the chapters and subtitles are moved there too.
+    let offset: Timestamp = clip_offset(start_time, options.timestamps);
+    let chapters: Option<PathBuf> = chapters_path(options, output);
+    let subtitles: Option<PathBuf> = subtitles_path(options, output);
+    let _temp = TempFiles::new(&[&chapters, &subtitles]);
+    write_chapters(
+        &offset_chapters(&options.chapters, offset),
+        chapters.as_deref(),
+    )?;
+    write_subtitles(options, subtitles.as_deref())?;
+    let mut maps: Vec<String> = Vec::new();
+    if subtitles.is_some() {
+        maps = vec![
+            String::from("-map"),
+            String::from("0:v:0"),
+            String::from("-map"),
+            String::from("0:a:0?"),
+        ];
+    }
+    let inputs: usize = if subtitles.is_some() { 2 } else { 1 };
+
+    let command = command::run(
+        Command::new("ffmpeg")
+            .arg("-v")
+            .arg(command::loglevel())
+            .arg("-ss")
+            .arg(start_time.to_string())
+            .arg("-i")
+            .arg(path)
+            .args(subtitles_input_args(subtitles.as_deref(), offset))
+            .args(chapters_args(chapters.as_deref(), inputs))
+            .args(timestamp_args(start_time, end_time, options.timestamps))
+            .args(maps)
+            .arg("-c")
+            .arg("copy")
+            .args(subtitles_output_args(subtitles.as_deref(), 1, options))
+            .arg("-y")
+            .args(tag_args(options))
+            .args(muxer_args(options))
+            .arg(output),
+    )
+    .map_err(Error::Run)?;
+
+    if !command.status.success() {
+        return Err(Error::Failure);
+    }
+    check_start(output, options.timestamps)
+}
+
+/**
+Cuts each of the *segments* out of the video and joins them, in order, into a single re-encoded output.
+
+Segments are crossfaded into each other when `crossfade` is set, otherwise they are concatenated.
+A title card, when set, is joined in front of the first segment the same way.
+
+# Example
+```rust, ignore
+use ff:mpeg;
+let options = CutOptions { crossfade: 0.5, ..Default::default() };
+mpeg::join(&chapter.verses("16,25")?, "nwt_43_Joh_ASL_03_r720P.mp4", "John_3-16_25.mp4", &options);
+```
+*/
+pub fn join(
+    segments: &[(Timestamp, Timestamp)],
+    path: &Path,
+    output: &str,
+    options: &CutOptions,
+) -> Result<(), Error> {
+    let chapters: Option<PathBuf> = chapters_path(options, output);
+    let subtitles: Option<PathBuf> = subtitles_path(options, output);
+    let args: Vec<String> = join_args(
+        segments,
+        path,
+        output,
+        options,
+        chapters.as_deref(),
+        subtitles.as_deref(),
+    )?;
+    let _temp = TempFiles::new(&[&chapters, &subtitles]);
+    write_chapters(&options.chapters, chapters.as_deref())?;
+    write_subtitles(options, subtitles.as_deref())?;
+
+    let command = command::run(Command::new("ffmpeg").args(args)).map_err(Error::Run)?;
 
     if !command.status.success() {
-        panic!("ffmpeg's exit status was FAILURE");
+        return Err(Error::Failure);
+    }
+    check_start(output, options.timestamps)
+}
+
+/// Returns the arguments selecting the end of a stream copied cut and how its timestamps are written.
+fn timestamp_args(
+    start_time: Timestamp,
+    end_time: Timestamp,
+    timestamps: Timestamps,
+) -> Vec<String> {
+    match timestamps {
+        Timestamps::Preserve => vec![
+            String::from("-to"),
+            end_time.to_string(),
+            String::from("-copyts"),
+            String::from("-avoid_negative_ts"),
+            String::from("1"),
+        ],
+        Timestamps::Reset => vec![
+            String::from("-t"),
+            (end_time - start_time).to_string(),
+            String::from("-avoid_negative_ts"),
+            String::from("make_zero"),
+        ],
+    }
+}
+
+/// Returns where a stream copied cut starting at `start_time` starts in its output.
+fn clip_offset(start_time: Timestamp, timestamps: Timestamps) -> Timestamp {
+    match timestamps {
+        Timestamps::Preserve => start_time,
+        Timestamps::Reset => Timestamp::ZERO,
+    }
+}
+
+/// Returns the chapters moved `offset` later, to where the clip starts in the output.
+fn offset_chapters(chapters: &[ClipChapter], offset: Timestamp) -> Vec<ClipChapter> {
+    let mut chapters: Vec<ClipChapter> = chapters.to_vec();
+    metadata::shift_chapters(&mut chapters, offset);
+    chapters
+}
+
+/**
+Checks with ffprobe that a reset output starts at zero.
+
+Nothing is checked when the timestamps are preserved, since those outputs are expected to start later.
+*/
+fn check_start(output: &str, timestamps: Timestamps) -> Result<(), Error> {
+    if timestamps == Timestamps::Preserve {
+        return Ok(());
+    }
+
+    let probe: Root = Root::new(Path::new(output)).map_err(|_| Error::Probe {
+        output: output.to_string(),
+    })?;
+    let start: Timestamp = probe.start_time().unwrap_or_default();
+
+    if start.abs() > Timestamp::from_seconds(START_TOLERANCE) {
+        return Err(Error::NonZeroStart {
+            output: output.to_string(),
+            start,
+        });
+    }
+    Ok(())
+}
+
+/// Returns the arguments passed to ffmpeg by [join].
+fn join_args(
+    segments: &[(Timestamp, Timestamp)],
+    path: &Path,
+    output: &str,
+    options: &CutOptions,
+    chapters: Option<&Path>,
+    subtitles: Option<&Path>,
+) -> Result<Vec<String>, Error> {
+    let mut args: Vec<String> = vec![String::from("-v"), String::from(command::loglevel())];
+    let joined: bool = segments.len() > 1 || options.title_card.is_some();
+    let crossfade: Timestamp = Timestamp::from_seconds(options.crossfade);
+
+    for (start_time, end_time) in segments {
+        let segment: Timestamp = *end_time - *start_time;
+        if joined && crossfade >= segment {
+            return Err(Error::CrossfadeTooLong { crossfade, segment });
+        }
+
+        args.push(String::from("-ss"));
+        args.push(start_time.to_string());
+        args.push(String::from("-t"));
+        args.push(segment.to_string());
+        args.push(String::from("-i"));
+        args.push(path.to_string_lossy().into_owned());
+    }
+    let mut inputs: usize = segments.len();
+    if let Some(card) = &options.title_card {
+        if options.crossfade >= card.duration {
+            return Err(Error::CrossfadeTooLong {
+                crossfade,
+                segment: Timestamp::from_seconds(card.duration),
+            });
+        }
+        args.append(&mut card.input_args());
+        inputs += card.inputs();
+    }
+    let subtitles_input: usize = inputs;
+    args.append(&mut subtitles_input_args(subtitles, Timestamp::ZERO));
+    if subtitles.is_some() {
+        inputs += 1;
+    }
+    args.append(&mut chapters_args(chapters, inputs));
+
+    let (filter, length) = join_filter(segments, options);
+    let mut filter: String = format!("{};{}", filter, fade_filter(length, options));
+    if let Some(card) = &options.title_card {
+        filter = format!(
+            "{};{}",
+            card.filter(segments.len(), &options.streams, options.no_audio),
+            filter
+        );
+    }
+
+    args.push(String::from("-filter_complex"));
+    args.push(filter);
+    args.push(String::from("-map"));
+    args.push(String::from("[v]"));
+    if !options.no_audio {
+        args.push(String::from("-map"));
+        args.push(String::from("[a]"));
+    }
+    args.append(&mut encoder_args(options));
+    args.append(&mut subtitles_output_args(
+        subtitles,
+        subtitles_input,
+        options,
+    ));
+    args.append(&mut tag_args(options));
+    args.append(&mut muxer_args(options));
+    args.push(String::from("-y"));
+    args.push(output.to_string());
+
+    Ok(args)
+}
+
+/**
+Returns the path of the temporary FFMETADATA file holding the chapters of the clip.
+
+Returns `None` when the clip has no chapters, in which case none are muxed into the output.
+*/
+fn chapters_path(options: &CutOptions, output: &str) -> Option<PathBuf> {
+    match options.chapters.is_empty() {
+        true => None,
+        false => Some(temp_path(output, "txt")),
+    }
+}
+
+/**
+Returns the path of the temporary SubRip file holding the subtitles of the clip.
+
+Returns `None` when the clip has no subtitles, in which case no subtitle track is muxed into the output.
+*/
+fn subtitles_path(options: &CutOptions, output: &str) -> Option<PathBuf> {
+    match options.subtitles.is_empty() {
+        true => None,
+        false => Some(temp_path(output, SubtitleFormat::Srt.extension())),
+    }
+}
+
+/// Returns the path of a temporary file, named after the process and the output.
+fn temp_path(output: &str, extension: &str) -> PathBuf {
+    let name: String = Path::new(output)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    std::env::temp_dir().join(format!(
+        "scriptx-{}-{}.{}",
+        std::process::id(),
+        name,
+        extension
+    ))
+}
+
+/// Writes the chapters of the clip to the temporary FFMETADATA file, if there is one.
+fn write_chapters(clip_chapters: &[ClipChapter], chapters: Option<&Path>) -> Result<(), Error> {
+    if let Some(c) = chapters {
+        metadata::write_ffmetadata(clip_chapters, c)?;
+    }
+    Ok(())
+}
+
+/// Writes the subtitles of the clip to the temporary SubRip file, if there is one.
+fn write_subtitles(options: &CutOptions, subtitles: Option<&Path>) -> Result<(), Error> {
+    if let Some(s) = subtitles {
+        fs::write(s, subtitles::srt(&options.subtitles))?;
+    }
+    Ok(())
+}
+
+/// The temporary files of a cut, removed when it returns however it ends.
+struct TempFiles {
+    paths: Vec<PathBuf>,
+}
+
+impl TempFiles {
+    /// Returns the guard removing the `paths` that are set.
+    fn new(paths: &[&Option<PathBuf>]) -> TempFiles {
+        TempFiles {
+            paths: paths.iter().filter_map(|p| p.as_ref().cloned()).collect(),
+        }
+    }
+}
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        for path in self.paths.iter() {
+            if path.exists() {
+                if let Err(e) = fs::remove_file(path) {
+                    debug!("Unable to remove {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the arguments adding the subtitle file as an input, moved `offset` later.
+fn subtitles_input_args(subtitles: Option<&Path>, offset: Timestamp) -> Vec<String> {
+    let mut args: Vec<String> = Vec::new();
+    if let Some(s) = subtitles {
+        if offset != Timestamp::ZERO {
+            args.push(String::from("-itsoffset"));
+            args.push(offset.to_string());
+        }
+        args.push(String::from("-i"));
+        args.push(s.to_string_lossy().into_owned());
+    }
+    args
+}
+
+/**
+Returns the arguments mapping the subtitle file into the output as a soft subtitle track.
+
+The `input` is the index of the file among ffmpeg's inputs. The track is written with the subtitle
+codec of the container, `mov_text` when the container is left for ffmpeg to guess.
+*/
+fn subtitles_output_args(
+    subtitles: Option<&Path>,
+    input: usize,
+    options: &CutOptions,
+) -> Vec<String> {
+    match subtitles {
+        Some(_) => vec![
+            String::from("-map"),
+            input.to_string(),
+            String::from("-c:s"),
+            options
+                .container
+                .map_or("mov_text", |c| c.subtitle_codec())
+                .to_string(),
+        ],
+        None => Vec::new(),
+    }
+}
+
+/**
+Returns the arguments adding the FFMETADATA file as an input and taking the output's chapters from it.
+
+The `input` is the index the file gets among ffmpeg's inputs. These are output options, except for the
+file itself, so they have to come after every other input.
+*/
+fn chapters_args(chapters: Option<&Path>, input: usize) -> Vec<String> {
+    match chapters {
+        Some(c) => vec![
+            String::from("-f"),
+            String::from("ffmetadata"),
+            String::from("-i"),
+            c.to_string_lossy().into_owned(),
+            String::from("-map_chapters"),
+            input.to_string(),
+        ],
+        None => Vec::new(),
+    }
+}
+
+/// Returns the codec and bitrate arguments used when re-encoding.
+fn encoder_args(options: &CutOptions) -> Vec<String> {
+    let preset: Preset = options.encoding();
+    let mut args: Vec<String> = vec![
+        String::from("-c:v"),
+        format::encoder(preset.video_codec).to_string(),
+    ];
+    if !preset.video_bitrate.is_empty() {
+        args.push(String::from("-b:v"));
+        args.push(preset.video_bitrate.to_string());
+    }
+    if !options.no_audio {
+        args.push(String::from("-c:a"));
+        args.push(format::encoder(preset.audio_codec).to_string());
+        if !preset.audio_bitrate.is_empty() {
+            args.push(String::from("-b:a"));
+            args.push(preset.audio_bitrate.to_string());
+        }
+    }
+    args
+}
+
+/// Returns the arguments writing the descriptive tags to the output.
+fn tag_args(options: &CutOptions) -> Vec<String> {
+    options
+        .tags
+        .iter()
+        .flat_map(|(key, value)| vec![String::from("-metadata"), format!("{}={}", key, value)])
+        .collect()
+}
+
+/// Returns the arguments selecting the muxer, empty when ffmpeg should guess it from the extension.
+fn muxer_args(options: &CutOptions) -> Vec<String> {
+    match options.container {
+        Some(c) => vec![String::from("-f"), c.muxer().to_string()],
+        None => Vec::new(),
+    }
+}
+
+/// Returns the length of the output made of the *segments*, with the title card added and the crossfades taken off.
+pub fn output_length(segments: &[(Timestamp, Timestamp)], options: &CutOptions) -> Timestamp {
+    join_filter(segments, options).1
+}
+
+/**
+Returns the filter joining the segments into the `[joined_v]` and `[joined_a]` pads, and the length of the joined output.
+
+The title card, when set, is taken from the `[card_v]` and `[card_a]` pads and joined in front of the segments.
+The card has square pixels, so the segments are given square pixels too before they are joined to it.
+*/
+fn join_filter(segments: &[(Timestamp, Timestamp)], options: &CutOptions) -> (String, Timestamp) {
+    let mut parts: Vec<(String, String, Timestamp)> = Vec::new();
+    let mut filters: Vec<String> = Vec::new();
+    if let Some(card) = &options.title_card {
+        parts.push((
+            String::from("[card_v]"),
+            String::from("[card_a]"),
+            Timestamp::from_seconds(card.duration),
+        ));
+    }
+    for (i, (start_time, end_time)) in segments.iter().enumerate() {
+        let video: String = match options.title_card {
+            Some(_) => {
+                filters.push(format!("[{}:v]setsar=1[segment{}_v]", i, i));
+                format!("[segment{}_v]", i)
+            }
+            None => format!("[{}:v]", i),
+        };
+        parts.push((video, format!("[{}:a]", i), *end_time - *start_time));
+    }
+
+    if options.crossfade <= 0.0 || parts.len() == 1 {
+        let mut filter = String::new();
+        for (video, audio, _) in parts.iter() {
+            filter.push_str(video);
+            if !options.no_audio {
+                filter.push_str(audio);
+            }
+        }
+        filter.push_str(&format!(
+            "concat=n={}:v=1:a={}[joined_v]",
+            parts.len(),
+            if options.no_audio { 0 } else { 1 }
+        ));
+        if !options.no_audio {
+            filter.push_str("[joined_a]");
+        }
+        filters.push(filter);
+        return (filters.join(";"), parts.iter().map(|(_, _, l)| *l).sum());
+    }
+
+    let crossfade: Timestamp = Timestamp::from_seconds(options.crossfade);
+    let (mut video, mut audio, mut length) = parts[0].clone();
+
+    for (i, (next_video, next_audio, segment)) in parts.iter().enumerate().skip(1) {
+        let last: bool = i == parts.len() - 1;
+        let video_out = if last {
+            String::from("[joined_v]")
+        } else {
+            format!("[v{}]", i)
+        };
+        let audio_out = if last {
+            String::from("[joined_a]")
+        } else {
+            format!("[a{}]", i)
+        };
+
+        filters.push(format!(
+            "{}{}xfade=transition=fade:duration={}:offset={}{}",
+            video,
+            next_video,
+            options.crossfade,
+            length - crossfade,
+            video_out
+        ));
+        if !options.no_audio {
+            filters.push(format!(
+                "{}{}acrossfade=d={}{}",
+                audio, next_audio, options.crossfade, audio_out
+            ));
+        }
+
+        length = length + *segment - crossfade;
+        video = video_out;
+        audio = audio_out;
+    }
+
+    (filters.join(";"), length)
+}
+
+/// Returns the filter scaling, labelling and fading the joined output, writing to the `[v]` and `[a]` pads.
+fn fade_filter(length: Timestamp, options: &CutOptions) -> String {
+    let mut video: Vec<String> = Vec::new();
+    let mut audio: Vec<String> = Vec::new();
+
+    if let Some(height) = options.encoding().height {
+        video.push(format!("scale=-2:{}", height));
+    }
+    if let Some(label) = &options.label {
+        video.push(label.filter());
+    }
+    if options.fade_in > 0.0 {
+        video.push(format!("fade=t=in:st=0:d={}", options.fade_in));
+        audio.push(format!("afade=t=in:st=0:d={}", options.fade_in));
+    }
+    if options.fade_out > 0.0 {
+        let start: Timestamp =
+            (length - Timestamp::from_seconds(options.fade_out)).max(Timestamp::ZERO);
+        video.push(format!("fade=t=out:st={}:d={}", start, options.fade_out));
+        audio.push(format!("afade=t=out:st={}:d={}", start, options.fade_out));
+    }
+    if video.is_empty() {
+        video.push(String::from("null"));
+    }
+    if audio.is_empty() {
+        audio.push(String::from("anull"));
+    }
+
+    let mut filter: String = format!("[joined_v]{}[v]", video.join(","));
+    if !options.no_audio {
+        filter.push_str(&format!(";[joined_a]{}[a]", audio.join(",")));
+    }
+    filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffwrappers::card::Background;
+    use crate::timestamp::t;
+
+    fn segments(times: &[(&str, &str)]) -> Vec<(Timestamp, Timestamp)> {
+        times.iter().map(|(s, e)| (t(s), t(e))).collect()
+    }
+
+    #[test]
+    fn test_needs_encoding() {
+        assert!(!CutOptions::default().needs_encoding());
+        let options = CutOptions {
+            fade_in: 1.0,
+            ..Default::default()
+        };
+        assert!(options.needs_encoding());
+    }
+
+    #[test]
+    fn test_join_filter_concat() {
+        let options = CutOptions::default();
+        let (filter, length) = join_filter(&segments(&[("10", "20"), ("30", "35")]), &options);
+        assert_eq!(
+            filter,
+            "[0:v][0:a][1:v][1:a]concat=n=2:v=1:a=1[joined_v][joined_a]"
+        );
+        assert_eq!(length, t("15"));
+    }
+
+    #[test]
+    fn test_join_filter_crossfade() {
+        let options = CutOptions {
+            crossfade: 1.0,
+            no_audio: true,
+            ..Default::default()
+        };
+        let (filter, length) = join_filter(
+            &segments(&[("0", "10"), ("20", "25"), ("30", "34")]),
+            &options,
+        );
+        assert_eq!(
+            filter,
+            "[0:v][1:v]xfade=transition=fade:duration=1:offset=9[v1];\
+             [v1][2:v]xfade=transition=fade:duration=1:offset=13[joined_v]"
+        );
+        assert_eq!(length, t("17"));
+    }
+
+    #[test]
+    fn test_join_filter_title_card() {
+        let options = CutOptions {
+            title_card: Some(TitleCard {
+                text: DrawText::default(),
+                background: Background::Color(String::from("black")),
+                duration: 3.0,
+            }),
+            ..Default::default()
+        };
+        let (filter, length) = join_filter(&segments(&[("10", "20")]), &options);
+        assert_eq!(
+            filter,
+            "[0:v]setsar=1[segment0_v];\
+             [card_v][card_a][segment0_v][0:a]concat=n=2:v=1:a=1[joined_v][joined_a]"
+        );
+        assert_eq!(length, t("13"));
+        assert_eq!(output_length(&segments(&[("10", "20")]), &options), t("13"));
+
+        let options = CutOptions {
+            crossfade: 0.5,
+            no_audio: true,
+            ..options
+        };
+        let (filter, length) = join_filter(&segments(&[("10", "20")]), &options);
+        assert_eq!(
+            filter,
+            "[0:v]setsar=1[segment0_v];\
+             [card_v][segment0_v]xfade=transition=fade:duration=0.5:offset=2.5[joined_v]"
+        );
+        assert_eq!(length, t("12.5"));
+    }
+
+    #[test]
+    fn test_fade_filter() {
+        let options = CutOptions {
+            fade_in: 0.5,
+            fade_out: 1.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            fade_filter(t("10"), &options),
+            "[joined_v]fade=t=in:st=0:d=0.5,fade=t=out:st=9:d=1[v];\
+             [joined_a]afade=t=in:st=0:d=0.5,afade=t=out:st=9:d=1[a]"
+        );
+    }
+
+    #[test]
+    fn test_fade_filter_scaled() {
+        let options = CutOptions {
+            preset: Some("phone-480p".parse().unwrap()),
+            no_audio: true,
+            ..Default::default()
+        };
+        assert_eq!(fade_filter(t("10"), &options), "[joined_v]scale=-2:480[v]");
+    }
+
+    #[test]
+    fn test_fade_filter_labelled() {
+        let options = CutOptions {
+            label: Some(DrawText {
+                text: String::from("John 3:16"),
+                enable: Some(String::from("lt(t,3)")),
+                ..Default::default()
+            }),
+            fade_in: 1.0,
+            no_audio: true,
+            ..Default::default()
+        };
+        assert!(options.needs_encoding());
+        assert_eq!(
+            fade_filter(t("10"), &options),
+            "[joined_v]drawtext=text=John 3\\\\:16:expansion=none:fontsize=24:fontcolor=white:x=10:y=10:\
+             enable='lt(t,3)',fade=t=in:st=0:d=1[v]"
+        );
+    }
+
+    #[test]
+    fn test_encoder_args() {
+        let options = CutOptions {
+            container: Some(Container::Webm),
+            ..Default::default()
+        };
+        assert_eq!(
+            encoder_args(&options),
+            vec!["-c:v", "libvpx-vp9", "-c:a", "libopus"]
+        );
+
+        let options = CutOptions {
+            preset: Some("projector-1080p".parse().unwrap()),
+            no_audio: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            encoder_args(&options),
+            vec!["-c:v", "libx264", "-b:v", "6M"]
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let streams = vec![Stream {
+            index: 0,
+            codec_type: String::from("video"),
+            codec_name: String::from("h264"),
+            ..Default::default()
+        }];
+        let copy = CutOptions {
+            container: Some(Container::Webm),
+            ..Default::default()
+        };
+        assert!(copy.check(&streams, false).is_err());
+        assert!(copy.check(&streams, true).is_ok());
+
+        let preset = CutOptions {
+            container: Some(Container::Webm),
+            preset: Some("archive".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(preset.check(&streams, false).is_err());
+    }
+
+    #[test]
+    fn test_timestamps_from_str() {
+        assert_eq!("reset".parse::<Timestamps>().unwrap(), Timestamps::Reset);
+        assert!("zero".parse::<Timestamps>().is_err());
+    }
+
+    #[test]
+    fn test_timestamp_args() {
+        assert_eq!(
+            timestamp_args(t("10"), t("12.5"), Timestamps::Preserve),
+            vec!["-to", "12.5", "-copyts", "-avoid_negative_ts", "1"]
+        );
+        assert_eq!(
+            timestamp_args(t("10"), t("12.5"), Timestamps::Reset),
+            vec!["-t", "2.5", "-avoid_negative_ts", "make_zero"]
+        );
+    }
+
+    #[test]
+    fn test_timestamp_args_exact() {
+        let start = Timestamp::from_ticks(17781930, "1/90000").unwrap();
+        let end = Timestamp::from_ticks(20363010, "1/90000").unwrap();
+        assert_eq!(
+            timestamp_args(start, end, Timestamps::Preserve)[1],
+            "226.255667"
+        );
+        assert_eq!(
+            timestamp_args(start, end, Timestamps::Reset)[1],
+            "28.678667"
+        );
+    }
+
+    #[test]
+    fn test_offset_chapters() {
+        let chapters = vec![ClipChapter {
+            start: t("0"),
+            end: t("28.662"),
+            title: String::from("John 3:16"),
+        }];
+        let preserved = offset_chapters(&chapters, clip_offset(t("197.597"), Timestamps::Preserve));
+        assert!(metadata::ffmetadata(&preserved).contains("START=197597\nEND=226259\n"));
+        let reset = offset_chapters(&chapters, clip_offset(t("197.597"), Timestamps::Reset));
+        assert_eq!(reset, chapters);
+    }
+
+    #[test]
+    fn test_temp_files_removed_on_error() {
+        let options = CutOptions {
+            chapters: vec![ClipChapter {
+                title: String::from("John 3:16"),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let chapters: Option<PathBuf> = chapters_path(&options, "temp-files.m4v");
+        let subtitles: Option<PathBuf> = Some(std::env::temp_dir().join("scriptx-missing/a.srt"));
+        let written = || -> Result<(), Error> {
+            let _temp = TempFiles::new(&[&chapters, &subtitles]);
+            write_chapters(&options.chapters, chapters.as_deref())?;
+            assert!(chapters.as_ref().unwrap().exists());
+            write_subtitles(&options, subtitles.as_deref())
+        };
+        assert!(written().is_err());
+        assert!(!chapters.unwrap().exists());
+    }
+
+    #[test]
+    fn test_written_chapters() {
+        let options = CutOptions {
+            chapters: vec![ClipChapter {
+                start: t("0"),
+                end: t("28.662"),
+                title: String::from("John 3:16"),
+            }],
+            ..Default::default()
+        };
+        let written = |timestamps: Timestamps| {
+            let path: PathBuf = chapters_path(&options, "written-chapters.m4v").unwrap();
+            let offset: Timestamp = clip_offset(t("197.597"), timestamps);
+            write_chapters(&offset_chapters(&options.chapters, offset), Some(&path)).unwrap();
+            let file: String = fs::read_to_string(&path).unwrap();
+            fs::remove_file(&path).unwrap();
+            file
+        };
+        assert_eq!(
+            written(Timestamps::Preserve),
+            ";FFMETADATA1\n\n[CHAPTER]\nTIMEBASE=1/1000\nSTART=197597\nEND=226259\ntitle=John 3:16\n"
+        );
+        assert_eq!(
+            written(Timestamps::Reset),
+            ";FFMETADATA1\n\n[CHAPTER]\nTIMEBASE=1/1000\nSTART=0\nEND=28662\ntitle=John 3:16\n"
+        );
+    }
+
+    #[test]
+    fn test_tag_args() {
+        let options = CutOptions {
+            tags: vec![(String::from("title"), String::from("John 3:16"))],
+            ..Default::default()
+        };
+        assert_eq!(tag_args(&options), vec!["-metadata", "title=John 3:16"]);
+    }
+
+    #[test]
+    fn test_join_args_subtitles() {
+        let options = CutOptions {
+            container: Some(Container::Mkv),
+            fade_in: 1.0,
+            ..Default::default()
+        };
+        let args = join_args(
+            &segments(&[("0", "10")]),
+            Path::new("in.mp4"),
+            "out.mkv",
+            &options,
+            Some(Path::new("chapters.txt")),
+            Some(Path::new("subtitles.srt")),
+        )
+        .unwrap();
+        assert_eq!(args[8..10], ["-i", "subtitles.srt"]);
+        assert_eq!(args[14..16], ["-map_chapters", "2"]);
+        let map: usize = args.iter().position(|a| a == "-c:s").unwrap();
+        assert_eq!(args[map - 2..map + 2], ["-map", "1", "-c:s", "srt"]);
+    }
+
+    #[test]
+    fn test_subtitles_input_args() {
+        assert!(subtitles_input_args(None, t("1")).is_empty());
+        assert_eq!(
+            subtitles_input_args(Some(Path::new("subtitles.srt")), t("197.597")),
+            vec!["-itsoffset", "197.597", "-i", "subtitles.srt"]
+        );
+    }
+
+    #[test]
+    fn test_chapters_args() {
+        assert!(chapters_args(None, 1).is_empty());
+        assert_eq!(
+            chapters_args(Some(Path::new("chapters.txt")), 2),
+            vec![
+                "-f",
+                "ffmetadata",
+                "-i",
+                "chapters.txt",
+                "-map_chapters",
+                "2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_join_args_crossfade_too_long() {
+        let options = CutOptions {
+            crossfade: 6.0,
+            ..Default::default()
+        };
+        let args = join_args(
+            &segments(&[("0", "10"), ("20", "25")]),
+            Path::new("in.mp4"),
+            "out.mp4",
+            &options,
+            None,
+            None,
+        );
+        assert!(args.is_err());
     }
 }
@@ -0,0 +1,204 @@
+/*!
+Animated previews
+
+Turns a verse, or a range of verses, into an animated GIF or WebP for places where a video can't
+be embedded. GIFs are rendered with a palette generated from the clip itself, which keeps them small
+and avoids the banding of ffmpeg's default palette.
+*/
+
+use crate::ffwrappers::command;
+use crate::timestamp::Timestamp;
+use std::{io, path::Path, process::Command, str::FromStr};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("ffmpeg's exit status was FAILURE")]
+    Failure,
+    #[error("Unable to run ffmpeg: {0}")]
+    Run(#[source] io::Error),
+    #[error("The preview format `{format:?}` is not supported")]
+    UnknownFormat { format: String },
+}
+
+/// The animated image formats a preview can be saved as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreviewFormat {
+    Gif,
+    Webp,
+}
+
+impl FromStr for PreviewFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gif" => Ok(PreviewFormat::Gif),
+            "webp" => Ok(PreviewFormat::Webp),
+            _ => Err(Error::UnknownFormat {
+                format: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Options controlling how a preview is rendered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PreviewOptions {
+    /// The format of the preview.
+    pub format: PreviewFormat,
+    /// The frame rate of the preview.
+    pub fps: u32,
+    /// The width of the preview in pixels, the height keeps the aspect ratio.
+    pub width: u32,
+    /// The longest the preview may run. `None` renders the whole span.
+    pub max_duration: Option<Timestamp>,
+}
+
+impl Default for PreviewOptions {
+    fn default() -> Self {
+        PreviewOptions {
+            format: PreviewFormat::Gif,
+            fps: 10,
+            width: 320,
+            max_duration: None,
+        }
+    }
+}
+
+/**
+Renders the span *start_time* to *end_time* of the video as an animated preview.
+
+# Example
+```rust, ignore
+use ff::preview;
+preview::render("197.597".parse()?, "226.259".parse()?, "nwt_43_Joh_ASL_03_r720P.mp4", "John_3-16.gif", &PreviewOptions::default());
+```
+*/
+pub fn render(
+    start_time: Timestamp,
+    end_time: Timestamp,
+    path: &Path,
+    output: &Path,
+    options: &PreviewOptions,
+) -> Result<(), Error> {
+    let command = command::run(
+        Command::new("ffmpeg").args(preview_args(start_time, end_time, path, output, options)),
+    )
+    .map_err(Error::Run)?;
+
+    if !command.status.success() {
+        return Err(Error::Failure);
+    }
+    Ok(())
+}
+
+/// Returns the arguments passed to ffmpeg by [render].
+fn preview_args(
+    start_time: Timestamp,
+    end_time: Timestamp,
+    path: &Path,
+    output: &Path,
+    options: &PreviewOptions,
+) -> Vec<String> {
+    let mut duration: Timestamp = end_time - start_time;
+    if let Some(max) = options.max_duration {
+        duration = duration.min(max);
+    }
+
+    let scale: String = format!(
+        "fps={},scale={}:-1:flags=lanczos",
+        options.fps, options.width
+    );
+    let mut args: Vec<String> = vec![
+        String::from("-v"),
+        String::from(command::loglevel()),
+        String::from("-ss"),
+        start_time.to_string(),
+        String::from("-t"),
+        duration.to_string(),
+        String::from("-i"),
+        path.to_string_lossy().into_owned(),
+    ];
+
+    match options.format {
+        PreviewFormat::Gif => {
+            args.push(String::from("-filter_complex"));
+            args.push(format!(
+                "[0:v]{},split[a][b];[a]palettegen=stats_mode=diff[p];[b][p]paletteuse=dither=bayer:bayer_scale=5",
+                scale
+            ));
+        }
+        PreviewFormat::Webp => {
+            args.push(String::from("-vf"));
+            args.push(scale);
+            args.push(String::from("-c:v"));
+            args.push(String::from("libwebp"));
+            args.push(String::from("-q:v"));
+            args.push(String::from("60"));
+            args.push(String::from("-compression_level"));
+            args.push(String::from("6"));
+        }
+    }
+
+    args.push(String::from("-an"));
+    args.push(String::from("-loop"));
+    args.push(String::from("0"));
+    args.push(String::from("-y"));
+    args.push(output.to_string_lossy().into_owned());
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::t;
+
+    #[test]
+    fn test_preview_format_from_str() {
+        assert_eq!(
+            "webp".parse::<PreviewFormat>().unwrap(),
+            PreviewFormat::Webp
+        );
+        assert!("mp4".parse::<PreviewFormat>().is_err());
+    }
+
+    #[test]
+    fn test_preview_args_gif() {
+        let options = PreviewOptions {
+            max_duration: Some(t("5")),
+            ..Default::default()
+        };
+        let args = preview_args(
+            t("10"),
+            t("30"),
+            Path::new("in.mp4"),
+            Path::new("out.gif"),
+            &options,
+        );
+        assert_eq!(args[4..6], ["-t", "5"]);
+        assert!(args.contains(&String::from(
+            "[0:v]fps=10,scale=320:-1:flags=lanczos,split[a][b];[a]palettegen=stats_mode=diff[p];[b][p]paletteuse=dither=bayer:bayer_scale=5"
+        )));
+    }
+
+    #[test]
+    fn test_preview_args_webp() {
+        let options = PreviewOptions {
+            format: PreviewFormat::Webp,
+            fps: 12,
+            width: 480,
+            max_duration: None,
+        };
+        let args = preview_args(
+            t("10"),
+            t("12.5"),
+            Path::new("in.mp4"),
+            Path::new("out.webp"),
+            &options,
+        );
+        assert_eq!(args[4..6], ["-t", "2.5"]);
+        assert!(args.contains(&String::from("libwebp")));
+        assert!(!args.contains(&String::from("-filter_complex")));
+    }
+}
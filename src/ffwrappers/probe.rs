@@ -8,11 +8,13 @@ stamps of each verse that is later used by [ffmpeg](https://ffmpeg.org/) to cut
 specific verses from the video file.
 */
 
-use crate::ffwrappers::errors::Errors;
+use crate::ffwrappers::command;
+use crate::ffwrappers::logging::debug;
+use crate::timestamp::Timestamp;
 use core::{f64, str};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::{path::Path, process::Command};
+use std::{io, path::Path, process::Command, str::FromStr};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -23,6 +25,26 @@ pub enum Error {
     PrefixNotMatch,
     #[error("Unable to parse verse number from {item:?} to i32")]
     VerseFromTitle { item: String },
+    #[error("The padded cut from {start} to {end} is empty")]
+    EmptySpan { start: Timestamp, end: Timestamp },
+    #[error(
+        "`{position:?}` is neither a time such as 1:23.5 nor a verse and offset such as 16+1.5s"
+    )]
+    InvalidPosition { position: String },
+    #[error("Unable to run ffprobe on {path:?}: {source}")]
+    Run {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("ffprobe was unable to read {path:?}. Check the path and try again")]
+    Failure { path: String },
+    #[error("Unable to parse what ffprobe reported for {path:?}: {source}")]
+    Json {
+        path: String,
+        #[source]
+        source: serde_json::Error,
+    },
 }
 #[derive(PartialEq, Debug)]
 enum VerseKind {
@@ -40,6 +62,11 @@ It contains a single struct of vector type which is a collection of all the chap
 pub struct Root {
     /// The `chapters` fields contains a vector of the type `Chapter` struct.
     pub chapters: Vec<Chapter>,
+    /// The `format` field contains information about the file as a whole, such as its duration.
+    pub format: Option<Format>,
+    /// The `streams` field contains a vector of the video, audio and other streams within the file.
+    #[serde(default)]
+    pub streams: Vec<Stream>,
 }
 /**
 The struct for the chapter
@@ -95,6 +122,160 @@ pub struct Tags {
     pub title: String,
 }
 
+/**
+Contains information about the container of the video file.
+
+The *duration* bounds how far a cut may be padded past the last verse and the *start_time* is used
+to check that an extracted clip starts at zero.
+*/
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Format {
+    /// The `duration` field, the length of the file in seconds.
+    #[serde(default)]
+    pub duration: String,
+    /// The `start_time` field, the timestamp of the first frame of the file in seconds.
+    #[serde(rename = "start_time", default)]
+    pub start_time: String,
+}
+
+/**
+Contains information about a single stream within the video file.
+
+Used to decide how a stream is handled when the cut has to be re-encoded.
+*/
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Stream {
+    /// The `index` of the stream within the file.
+    pub index: i64,
+    /// The `codec_type` field, for example `video` or `audio`.
+    #[serde(rename = "codec_type", default)]
+    pub codec_type: String,
+    /// The `codec_name` field, for example `h264` or `aac`.
+    #[serde(rename = "codec_name", default)]
+    pub codec_name: String,
+    /// The `width` of a video stream in pixels.
+    pub width: Option<u32>,
+    /// The `height` of a video stream in pixels.
+    pub height: Option<u32>,
+    /// The `r_frame_rate` field, the frame rate of a video stream as a fraction, for example `30000/1001`.
+    #[serde(rename = "r_frame_rate", default)]
+    pub r_frame_rate: String,
+    /// The `sample_rate` field of an audio stream, for example `48000`.
+    #[serde(rename = "sample_rate", default)]
+    pub sample_rate: String,
+    /// The `channel_layout` field of an audio stream, for example `stereo`.
+    #[serde(rename = "channel_layout", default)]
+    pub channel_layout: String,
+}
+
+/**
+Adjustments applied to the *start* and *end* times of a cut.
+
+All values are in seconds. A positive `start` pads the cut earlier and a positive `end` pads it later,
+negative values trim the cut instead. The `offset` shifts the whole cut and is applied before the padding.
+*/
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Padding {
+    /// Seconds added before the start of the cut.
+    pub start: f64,
+    /// Seconds added after the end of the cut.
+    pub end: f64,
+    /// Seconds the cut is shifted by, used to correct files whose chapters are out of sync.
+    pub offset: f64,
+    /// When `true` the padding never reaches past the verses neighbouring the cut.
+    pub clamp_to_neighbours: bool,
+}
+
+/**
+A point in the file chosen on the command line, either a time or a time relative to a verse.
+
+Relative times are written as the verse, a sign and a number of seconds, such as `16+1.5s` or `18-0.5s`.
+They are measured from the start of the verse when they start a cut, and from its end when they end one.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum Position {
+    /// A time from the start of the file.
+    Time(Timestamp),
+    /// A time relative to a verse.
+    Verse {
+        /// The verse, for example `16`.
+        verse: String,
+        /// How far after, or when negative before, the verse the position is.
+        offset: Timestamp,
+    },
+}
+
+impl FromStr for Position {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = Regex::new(r"^(\d+)\s*([+-])\s*(\d*\.?\d+)s$").unwrap(); // Matches relative times such as '16+1.5s'.
+        let error = || Error::InvalidPosition {
+            position: s.to_string(),
+        };
+
+        match pattern.captures(s.trim()) {
+            Some(c) => {
+                let offset: Timestamp = c[3].parse().map_err(|_| error())?;
+                Ok(Position::Verse {
+                    verse: c[1].to_string(),
+                    offset: match &c[2] {
+                        "-" => Timestamp::ZERO - offset,
+                        _ => offset,
+                    },
+                })
+            }
+            None => Timestamp::from_clock(s)
+                .map(Position::Time)
+                .map_err(|_| error()),
+        }
+    }
+}
+
+impl Chapter {
+    /**
+    Returns the exact *start* and *end* of the chapter.
+
+    The `start_time` and `end_time` ffprobe reports are what place the chapter. They are rounded to
+    the microsecond, so where the `start` and `end` ticks agree with them to within a microsecond
+    the exact ticks are used instead. Ticks that disagree, as in files whose chapters were edited
+    without recomputing them, are ignored, and a missing or unreadable time falls back on the ticks.
+    */
+    pub fn times(&self) -> (Timestamp, Timestamp) {
+        (
+            chapter_time(&self.start_time, self.start, &self.time_base),
+            chapter_time(&self.end_time, self.end, &self.time_base),
+        )
+    }
+
+    /// Returns whether the `start` and `end` ticks disagree with the reported `start_time` and `end_time`.
+    pub fn tick_mismatch(&self) -> bool {
+        let mismatch = |reported: &str, ticks: i64| match (
+            reported.parse::<Timestamp>(),
+            Timestamp::from_ticks(ticks, &self.time_base),
+        ) {
+            (Ok(reported), Ok(ticks)) => (reported - ticks).abs() > Timestamp::MICROSECOND,
+            _ => false,
+        };
+        mismatch(&self.start_time, self.start) || mismatch(&self.end_time, self.end)
+    }
+}
+
+/// Returns the `reported` time of a chapter boundary, or its exact `ticks` when they agree with it.
+fn chapter_time(reported: &str, ticks: i64, time_base: &str) -> Timestamp {
+    match (
+        reported.parse::<Timestamp>(),
+        Timestamp::from_ticks(ticks, time_base),
+    ) {
+        (Ok(reported), Ok(exact)) if (reported - exact).abs() <= Timestamp::MICROSECOND => exact,
+        (Ok(reported), _) => reported,
+        (Err(_), Ok(exact)) => exact,
+        (Err(_), Err(_)) => Timestamp::ZERO,
+    }
+}
+
 impl Root {
     /**
     Returns the Root struct when given a path to a video file.
@@ -113,28 +294,40 @@ impl Root {
     let chapter:Root = probe::new("nwt_43_Joh_ASL_03_r720P.mp4");
     ```
     */
-    pub fn new(path: &Path) -> Result<Root, Errors> {
-        let probe = Command::new("ffprobe")
-            .arg("-v")
-            .arg("quiet")
-            .arg("-print_format")
-            .arg("json")
-            .arg("-show_chapters")
-            .arg("-i")
-            .arg(path)
-            .output()
-            .unwrap();
+    pub fn new(path: &Path) -> Result<Root, Error> {
+        let name = || path.to_string_lossy().into_owned();
+        let probe = command::run(
+            Command::new("ffprobe")
+                .arg("-v")
+                .arg(command::loglevel())
+                .arg("-print_format")
+                .arg("json")
+                .arg("-show_chapters")
+                .arg("-show_format")
+                .arg("-show_streams")
+                .arg("-i")
+                .arg(path),
+        )
+        .map_err(|source| Error::Run {
+            path: name(),
+            source,
+        })?;
 
         if !probe.status.success() {
-            eprint!(
-                "The file, {}, was not found by ffprobe. Check path and try again. ",
-                path.to_str().unwrap()
-            );
-            return Err(Errors::FileError);
+            return Err(Error::Failure { path: name() });
         }
 
-        let c: Root =
-            serde_json::from_slice(&probe.stdout).expect("Error during JSON parsing of file.");
+        let c: Root = serde_json::from_slice(&probe.stdout).map_err(|source| Error::Json {
+            path: name(),
+            source,
+        })?;
+        debug!(
+            "Probed {}: {} chapters, {} streams, lasting {}s",
+            path.display(),
+            c.chapters.len(),
+            c.streams.len(),
+            c.duration().unwrap_or_default()
+        );
         Ok(c)
     }
 
@@ -175,15 +368,17 @@ impl Root {
     #                 },
     #             },
     #         ]
-    #     }
+    #     },
+    #     format: None,
+    #     streams: Vec::new(),
     # };
 
     // let chapter:Root = probe::new("nwt_43_Joh_ASL_03_r720P.mp4");
-    assert_eq!(chapter.verse("16"), (197.597, 226.259)); // Returned the `start_time` and `end_time`.
+    assert_eq!(chapter.verse("16").unwrap().0.to_string(), "197.597"); // Returned the exact *start* and *end*.
     ```
 
      */
-    pub fn verse(&self, verse: &str) -> Result<(f64, f64), Error> {
+    pub fn verse(&self, verse: &str) -> Result<(Timestamp, Timestamp), Error> {
         let kind: VerseKind = verse_kind(verse);
         match kind {
             VerseKind::SingleVerse => Ok(self.return_single_verse(verse)?),
@@ -192,16 +387,15 @@ impl Root {
     }
 
     /// Returns a tuple with the _start_ and _end_ time for a singe verse.
-    fn return_single_verse(&self, verse: &str) -> Result<(f64, f64), Error> {
-        Ok(self
-            .get_times(self.find_verse_id(format!("{}{}", self.get_prefix()?, verse).as_str())?)?)
+    fn return_single_verse(&self, verse: &str) -> Result<(Timestamp, Timestamp), Error> {
+        self.get_times(self.find_verse_id(format!("{}{}", self.get_prefix()?, verse).as_str())?)
     }
 
     /// Returns a tuple with the _start_ and _end_ time for a range of verses.
-    fn return_range_verse(&self, verse: &str) -> Result<(f64, f64), Error> {
+    fn return_range_verse(&self, verse: &str) -> Result<(Timestamp, Timestamp), Error> {
         let range: (&str, &str) = range_split(verse)?;
-        let start_time: f64 = self.return_single_verse(range.0).unwrap().0;
-        let end_time: f64 = self.return_single_verse(range.1).unwrap().1;
+        let start_time: Timestamp = self.return_single_verse(range.0)?.0;
+        let end_time: Timestamp = self.return_single_verse(range.1)?.1;
 
         Ok((start_time, end_time))
     }
@@ -220,10 +414,10 @@ impl Root {
     }
 
     /// Returns a tuple of the *start* and *end* time for the chapter in which the *id* has been provided for.
-    fn get_times(&self, id: i64) -> Result<(f64, f64), Error> {
+    fn get_times(&self, id: i64) -> Result<(Timestamp, Timestamp), Error> {
         for i in self.chapters.iter() {
             if i.id == id {
-                return Ok((i.start_time.parse().unwrap(), i.end_time.parse().unwrap()));
+                return Ok(i.times());
             }
         }
         unreachable!()
@@ -259,10 +453,10 @@ impl Root {
     }
 
     /// Returns all the verses' start and end times in a vector.
-    pub fn get_all_verses(&self) -> Result<Vec<(f64, f64)>, Error> {
+    pub fn get_all_verses(&self) -> Result<Vec<(Timestamp, Timestamp)>, Error> {
         // let prefix: &str = self.get_prefix(); // Example of prefix: 'John 3:'.
         let last_verse: i32 = get_verse_from_title(self.get_last_chapter()?.tags.title.as_str())?;
-        let mut all_verses: Vec<(f64, f64)> = Vec::new(); // tuple of all the start/end times for the entire chapter.
+        let mut all_verses: Vec<(Timestamp, Timestamp)> = Vec::new(); // tuple of all the start/end times for the entire chapter.
 
         for i in 1..last_verse {
             all_verses.push(self.verse(&i.to_string())?);
@@ -270,6 +464,133 @@ impl Root {
 
         Ok(all_verses)
     }
+
+    /**
+    Returns the verses of the file split into ranges of `size` verses, for example `1-4`, `5-8` and `9`.
+
+    Only the chapters whose title shares the prefix of the last chapter, such as `John 3:`, are counted.
+    */
+    pub fn groups(&self, size: usize) -> Result<Vec<String>, Error> {
+        let prefix: &str = self.get_prefix()?;
+        let mut verses: Vec<u32> = self
+            .chapters
+            .iter()
+            .filter_map(|c| c.tags.title.strip_prefix(prefix)?.trim().parse().ok())
+            .collect();
+        verses.sort_unstable();
+        verses.dedup();
+
+        Ok(verses
+            .chunks(size.max(1))
+            .map(|group| match group {
+                [verse] => verse.to_string(),
+                _ => format!("{}-{}", group[0], group[group.len() - 1]),
+            })
+            .collect())
+    }
+
+    /**
+    Returns the *start* and *end* times for each verse, or range of verses, in a comma separated list.
+
+    For example, `3,5-7` returns the times for verse 3 followed by the times for verses 5 through 7.
+    */
+    pub fn verses(&self, verses: &str) -> Result<Vec<(Timestamp, Timestamp)>, Error> {
+        verses.split(',').map(|v| self.verse(v.trim())).collect()
+    }
+
+    /**
+    Returns the *start* and *end* times of the cut between two positions.
+
+    A verse-relative `start` is measured from the start of its verse and a verse-relative `end` from
+    the end of its verse.
+
+    ## Example
+
+    ```rust, ignore
+    let times = chapter.span(&"16+1.5s".parse()?, &"1:00:00".parse()?)?;
+    assert_eq!(times.0.to_string(), "199.097");
+    ```
+    */
+    pub fn span(&self, start: &Position, end: &Position) -> Result<(Timestamp, Timestamp), Error> {
+        let start: Timestamp = match start {
+            Position::Time(t) => *t,
+            Position::Verse { verse, offset } => self.verse(verse)?.0 + *offset,
+        };
+        let end: Timestamp = match end {
+            Position::Time(t) => *t,
+            Position::Verse { verse, offset } => self.verse(verse)?.1 + *offset,
+        };
+
+        if start >= end {
+            return Err(Error::EmptySpan { start, end });
+        }
+        Ok((start, end))
+    }
+
+    /// Returns `true` if the file contains at least one audio stream.
+    pub fn has_audio(&self) -> bool {
+        self.streams.iter().any(|s| s.codec_type == "audio")
+    }
+
+    /// Returns the start time of the file if ffprobe reported one.
+    pub fn start_time(&self) -> Option<Timestamp> {
+        self.format.as_ref()?.start_time.parse().ok()
+    }
+
+    /// Returns the duration of the file if ffprobe reported one.
+    pub fn duration(&self) -> Option<Timestamp> {
+        self.format.as_ref()?.duration.parse().ok()
+    }
+
+    /**
+    Returns the *start* and *end* times after applying the `padding` to them.
+
+    The result never starts before the beginning of the file nor ends past its duration. When
+    `clamp_to_neighbours` is set, the padding never reaches into the neighbouring verses: the cut
+    starts no earlier than the end of the verse before it and ends no later than the start of the
+    verse after it. The `offset` shifts the times first, and the neighbouring verses are those of the
+    shifted times.
+
+    ## Example
+
+    ```rust, ignore
+    let padding = Padding { start: 0.5, end: 0.25, ..Default::default() };
+    let times = chapter.pad(chapter.verse("16")?, &padding)?;
+    assert_eq!(times.0.to_string(), "197.097");
+    ```
+    */
+    pub fn pad(
+        &self,
+        times: (Timestamp, Timestamp),
+        padding: &Padding,
+    ) -> Result<(Timestamp, Timestamp), Error> {
+        let offset: Timestamp = Timestamp::from_seconds(padding.offset);
+        let (start_time, end_time) = (times.0 + offset, times.1 + offset);
+        let mut lower: Timestamp = Timestamp::ZERO;
+        let mut upper: Option<Timestamp> = self.duration();
+
+        if padding.clamp_to_neighbours {
+            for chapter in self.chapters.iter() {
+                let (chapter_start, chapter_end) = chapter.times();
+                if chapter_end <= start_time && chapter_end > lower {
+                    lower = chapter_end;
+                }
+                if chapter_start >= end_time && upper.map_or(true, |u| chapter_start < u) {
+                    upper = Some(chapter_start);
+                }
+            }
+        }
+
+        let start: Timestamp = (start_time - Timestamp::from_seconds(padding.start)).max(lower);
+        let end: Timestamp = end_time + Timestamp::from_seconds(padding.end);
+        let end: Timestamp = upper.map_or(end, |u| end.min(u));
+
+        if start >= end {
+            return Err(Error::EmptySpan { start, end });
+        }
+
+        Ok((start, end))
+    }
 }
 
 /**
@@ -292,6 +613,27 @@ fn get_verse_from_title(title: &str) -> Result<i32, Error> {
     }
 }
 
+/**
+Splits a full title into its book, chapter and verse.
+
+Returns `None` when the title doesn't follow the "Book C:V" pattern.
+
+## Example
+```rust, ignore
+assert_eq!(split_title("1 John 3:16"), Some(("1 John", 3, 16)));
+```
+*/
+pub fn split_title(title: &str) -> Option<(&str, u32, u32)> {
+    let pattern = Regex::new(r"^(.+?)\s*(\d+):(\d+)$").unwrap(); // Matches titles such as 'John 3:16' or 'Ps. 83:13'.
+    let captures = pattern.captures(title.trim())?;
+
+    Some((
+        captures.get(1)?.as_str(),
+        captures.get(2)?.as_str().parse().ok()?,
+        captures.get(3)?.as_str().parse().ok()?,
+    ))
+}
+
 /// Determines whether the verse is single or part of a range.
 fn verse_kind(verse: &str) -> VerseKind {
     if verse.contains('-') {
@@ -319,7 +661,9 @@ fn range_split(verse: &str) -> Result<(&str, &str), Error> {
 
 #[cfg(test)]
 mod tests {
+    use super::fixtures::init_struct_1;
     use super::*;
+    use crate::timestamp::t;
 
     #[test]
     fn test_get_verse_from_title() {
@@ -333,20 +677,28 @@ mod tests {
         assert_eq!(get_verse_from_title(title_0).unwrap(), 32);
     }
 
+    #[test]
+    fn test_split_title() {
+        assert_eq!(split_title("John 3:16"), Some(("John", 3, 16)));
+        assert_eq!(split_title("Ps. 83:13"), Some(("Ps.", 83, 13)));
+        assert_eq!(split_title("1 John 4:8"), Some(("1 John", 4, 8)));
+        assert_eq!(split_title("Introduction"), None);
+    }
+
     #[test]
     fn test_verse() {
         let root = init_struct_1();
-        assert_eq!(root.verse("16").unwrap(), (197.597, 226.259));
-        assert_eq!(root.verse("17").unwrap(), (226.259, 241.908));
-        assert_eq!(root.verse("25").unwrap(), (358.658, 374.741));
-        assert_eq!(root.verse("26").unwrap(), (374.741, 394.561));
+        assert_eq!(root.verse("16").unwrap(), (t("197.597"), t("226.259")));
+        assert_eq!(root.verse("17").unwrap(), (t("226.259"), t("241.908")));
+        assert_eq!(root.verse("25").unwrap(), (t("358.658"), t("374.741")));
+        assert_eq!(root.verse("26").unwrap(), (t("374.741"), t("394.561")));
     }
 
     #[test]
     #[should_panic]
     fn test_verse_not_found() {
         let root = init_struct_1();
-        assert_eq!(root.verse("27").unwrap(), (197.597, 226.259));
+        assert_eq!(root.verse("27").unwrap(), (t("197.597"), t("226.259")));
     }
 
     #[test]
@@ -383,29 +735,35 @@ mod tests {
     #[test]
     fn test_find_times() {
         let r: Root = init_struct_1();
-        assert_eq!(r.get_times(16).unwrap(), (197.597, 226.259));
+        assert_eq!(r.get_times(16).unwrap(), (t("197.597"), t("226.259")));
     }
     #[test]
     fn test_return_range_verse() {
         let r: Root = init_struct_1();
-        assert_eq!(r.return_range_verse("16-17").unwrap(), (197.597, 241.908))
+        assert_eq!(
+            r.return_range_verse("16-17").unwrap(),
+            (t("197.597"), t("241.908"))
+        )
     }
 
     #[test]
     fn test_return_single_verse() {
         let r: Root = init_struct_1();
-        assert_eq!(r.return_single_verse("16").unwrap(), (197.597, 226.259))
+        assert_eq!(
+            r.return_single_verse("16").unwrap(),
+            (t("197.597"), t("226.259"))
+        )
     }
 
     #[test]
     fn test_verse_single() {
         let r: Root = init_struct_1();
-        assert_eq!(r.verse("16").unwrap(), (197.597, 226.259))
+        assert_eq!(r.verse("16").unwrap(), (t("197.597"), t("226.259")))
     }
     #[test]
     fn test_verse_range() {
         let r: Root = init_struct_1();
-        assert_eq!(r.verse("16-17").unwrap(), (197.597, 241.908))
+        assert_eq!(r.verse("16-17").unwrap(), (t("197.597"), t("241.908")))
     }
 
     #[test]
@@ -424,10 +782,238 @@ mod tests {
     #[ignore = "Need to finish writing the test."]
     fn test_get_all_verses() {
         let f = init_struct_1();
-        assert_eq!(f.get_all_verses().unwrap(), vec![(197.597000, 4226.259000)]);
+        assert_eq!(
+            f.get_all_verses().unwrap(),
+            vec![(t("197.597000"), t("4226.259000"))]
+        );
+    }
+
+    #[test]
+    fn test_verses() {
+        let r: Root = init_struct_1();
+        assert_eq!(
+            r.verses("16, 25-26").unwrap(),
+            vec![(t("197.597"), t("226.259")), (t("358.658"), t("394.561"))]
+        );
+    }
+
+    #[test]
+    fn test_position_from_str() {
+        assert_eq!(
+            "16+1.5s".parse::<Position>().unwrap(),
+            Position::Verse {
+                verse: String::from("16"),
+                offset: t("1.5"),
+            }
+        );
+        assert_eq!(
+            "18-0.5s".parse::<Position>().unwrap(),
+            Position::Verse {
+                verse: String::from("18"),
+                offset: t("-0.5"),
+            }
+        );
+        assert_eq!(
+            "3:16.5".parse::<Position>().unwrap(),
+            Position::Time(t("196.5"))
+        );
+        assert_eq!("42".parse::<Position>().unwrap(), Position::Time(t("42")));
+        assert!("16-18".parse::<Position>().is_err());
+    }
+
+    #[test]
+    fn test_span() {
+        let r: Root = init_struct_1();
+        let position = |p: &str| p.parse::<Position>().unwrap();
+        assert_eq!(
+            r.span(&position("16+1.5s"), &position("26-0.5s")).unwrap(),
+            (t("199.097"), t("394.061"))
+        );
+        assert_eq!(
+            r.span(&position("3:20"), &position("17+0s")).unwrap(),
+            (t("200"), t("241.908"))
+        );
+        assert!(r.span(&position("17+0s"), &position("16+0s")).is_err());
+        assert!(r.span(&position("27+0s"), &position("400")).is_err());
+    }
+
+    #[test]
+    fn test_groups() {
+        let r: Root = init_struct_1();
+        assert_eq!(r.groups(3).unwrap(), vec!["16-25", "26"]);
+        assert_eq!(r.groups(1).unwrap(), vec!["16", "17", "25", "26"]);
+        assert_eq!(r.groups(10).unwrap(), vec!["16-26"]);
+    }
+
+    #[test]
+    fn test_verse_range_not_found() {
+        let r: Root = init_struct_1();
+        assert!(r.verse("16-30").is_err());
+    }
+
+    #[test]
+    fn test_has_audio() {
+        let mut r: Root = init_struct_1();
+        assert!(r.has_audio());
+        r.streams.retain(|s| s.codec_type != "audio");
+        assert!(!r.has_audio());
+    }
+
+    #[test]
+    fn test_pad() {
+        let r: Root = init_struct_1();
+        let padding = Padding {
+            start: 0.5,
+            end: 0.25,
+            ..Default::default()
+        };
+        let (start, end) = r.pad(r.verse("17").unwrap(), &padding).unwrap();
+        assert_eq!((start, end), (t("225.759"), t("242.158")));
+    }
+
+    #[test]
+    fn test_pad_offset() {
+        let r: Root = init_struct_1();
+        let padding = Padding {
+            offset: -1.0,
+            ..Default::default()
+        };
+        let (start, end) = r.pad(r.verse("26").unwrap(), &padding).unwrap();
+        assert_eq!((start, end), (t("373.741"), t("393.561")));
     }
 
-    fn init_struct_1() -> Root {
+    #[test]
+    fn test_pad_clamped_to_file() {
+        let r: Root = init_struct_1();
+        let padding = Padding {
+            end: 30.0,
+            ..Default::default()
+        };
+        assert_eq!(r.pad(r.verse("26").unwrap(), &padding).unwrap().1, t("400"));
+    }
+
+    #[test]
+    fn test_pad_clamped_to_neighbours() {
+        let r: Root = init_struct_1();
+        let padding = Padding {
+            start: 60.0,
+            end: 200.0,
+            offset: 0.0,
+            clamp_to_neighbours: true,
+        };
+        assert_eq!(
+            r.pad(r.verse("17").unwrap(), &padding).unwrap(),
+            (t("226.259"), t("358.658"))
+        );
+    }
+
+    #[test]
+    fn test_pad_offset_clamped_to_neighbours() {
+        let r: Root = init_struct_1();
+        let padding = Padding {
+            offset: -1.0,
+            clamp_to_neighbours: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            r.pad(r.verse("17").unwrap(), &padding).unwrap(),
+            (t("225.259"), t("240.908"))
+        );
+
+        let padding = Padding {
+            start: 2.0,
+            end: 0.5,
+            offset: 1.0,
+            clamp_to_neighbours: true,
+        };
+        assert_eq!(
+            r.pad(r.verse("17").unwrap(), &padding).unwrap(),
+            (t("226.259"), t("243.408"))
+        );
+    }
+
+    #[test]
+    fn test_pad_empty() {
+        let r: Root = init_struct_1();
+        let padding = Padding {
+            start: -10.0,
+            end: -10.0,
+            ..Default::default()
+        };
+        assert!(r.pad(r.verse("17").unwrap(), &padding).is_err());
+    }
+
+    #[test]
+    fn test_times_exact_ticks() {
+        let chapter = Chapter {
+            id: 1,
+            time_base: String::from("1/90000"),
+            start: 1096095,
+            start_time: String::from("12.178833"),
+            end: 2700000,
+            end_time: String::from("30.000000"),
+            tags: Tags {
+                title: String::from("John 3:1"),
+            },
+        };
+        let (start, end) = chapter.times();
+        assert_eq!(start, Timestamp::from_ticks(1096095, "1/90000").unwrap());
+        assert_ne!(start, "12.178833".parse().unwrap());
+        assert_eq!(end, "30".parse().unwrap());
+        assert!(!chapter.tick_mismatch());
+    }
+
+    #[test]
+    fn test_times_reported_wins() {
+        // Chapter 17's start ticks repeat chapter 16's; its start_time places it.
+        let r = init_struct_1();
+        let chapter = &r.chapters[1];
+        assert!(chapter.tick_mismatch());
+        assert_eq!(
+            chapter.times(),
+            ("226.259".parse().unwrap(), "241.908".parse().unwrap())
+        );
+
+        let unreported = Chapter {
+            start_time: String::new(),
+            ..chapter.clone()
+        };
+        assert_eq!(unreported.times().0, "197.597".parse().unwrap());
+        assert!(!unreported.tick_mismatch());
+    }
+}
+
+/// Chapters shared by the tests of the modules that read them.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+    use crate::timestamp::t;
+
+    /// Returns a chapter reported from `start` to `end` seconds, with matching ticks in milliseconds.
+    pub fn chapter(id: i64, start: &str, end: &str, title: &str) -> Chapter {
+        Chapter {
+            id,
+            time_base: String::from("1/1000"),
+            start: t(start).to_ticks("1/1000").unwrap(),
+            start_time: format!("{:.6}", t(start)),
+            end: t(end).to_ticks("1/1000").unwrap(),
+            end_time: format!("{:.6}", t(end)),
+            tags: Tags {
+                title: String::from(title),
+            },
+        }
+    }
+
+    /// Returns a file with only the given chapters.
+    pub fn root(chapters: Vec<Chapter>) -> Root {
+        Root {
+            chapters,
+            ..Default::default()
+        }
+    }
+
+    /// Returns four verses of John 3 in a 720p file, the ticks of John 3:17 disagreeing with its reported start.
+    pub fn init_struct_1() -> Root {
         let root_struct: Root = Root {
             chapters: {
                 vec![
@@ -477,6 +1063,29 @@ mod tests {
                     },
                 ]
             },
+            format: Some(Format {
+                duration: String::from("400.000000"),
+                start_time: String::from("0.000000"),
+            }),
+            streams: vec![
+                Stream {
+                    index: 0,
+                    codec_type: String::from("video"),
+                    codec_name: String::from("h264"),
+                    width: Some(1280),
+                    height: Some(720),
+                    r_frame_rate: String::from("30000/1001"),
+                    ..Default::default()
+                },
+                Stream {
+                    index: 1,
+                    codec_type: String::from("audio"),
+                    codec_name: String::from("aac"),
+                    sample_rate: String::from("48000"),
+                    channel_layout: String::from("stereo"),
+                    ..Default::default()
+                },
+            ],
         };
 
         root_struct
@@ -501,6 +1110,8 @@ Structure of the structs used in ff::probe
 
 struct Root {
 chapters: Vec<Chapter>,
+format: Option<Format>,
+streams: Vec<Stream>,
 }
 struct Chapter {
 id: i64,
@@ -514,4 +1125,18 @@ tags: Tags,
 pub struct Tags {
 title: String, // The verse(s) being search for is compared to this field.
 }
+pub struct Format {
+duration: String,
+start_time: String,
+}
+pub struct Stream {
+index: i64,
+codec_type: String,
+codec_name: String,
+width: Option<u32>,
+height: Option<u32>,
+r_frame_rate: String,
+sample_rate: String,
+channel_layout: String,
+}
 */
@@ -0,0 +1,202 @@
+/*!
+Contact sheets
+
+Renders a tiled image of frames taken from every chapter of a video file, each labelled with the
+chapter's title and timestamp, so the verse segmentation of a file can be checked at a glance.
+*/
+
+use crate::chapters::clock;
+use crate::ffwrappers::command;
+use crate::ffwrappers::drawtext::DrawText;
+use crate::ffwrappers::probe::Root;
+use crate::timestamp::Timestamp;
+use std::{io, path::Path, process::Command};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("ffmpeg's exit status was FAILURE")]
+    Failure,
+    #[error("Unable to run ffmpeg: {0}")]
+    Run(#[source] io::Error),
+    #[error("The file has no chapters to put on a contact sheet")]
+    NoChapters,
+}
+
+/// Options controlling the layout of a contact sheet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SheetOptions {
+    /// The number of tiles in each row.
+    pub columns: u32,
+    /// The number of frames taken from each chapter, spread evenly over it.
+    pub frames_per_chapter: u32,
+    /// The width of each tile in pixels.
+    pub width: u32,
+    /// The path to the font used for the labels. `None` uses ffmpeg's default font.
+    pub font: Option<String>,
+}
+
+impl Default for SheetOptions {
+    fn default() -> Self {
+        SheetOptions {
+            columns: 5,
+            frames_per_chapter: 1,
+            width: 320,
+            font: None,
+        }
+    }
+}
+
+/// A frame on the contact sheet.
+#[derive(Debug, Clone, PartialEq)]
+struct Tile {
+    /// The time of the frame.
+    time: Timestamp,
+    /// The label drawn onto the frame.
+    label: String,
+}
+
+/**
+Renders a contact sheet of the chapters of `root` into the image `output`.
+
+# Example
+```rust, ignore
+use ff::sheet;
+let chapters: Root = Root::new("nwt_43_Joh_ASL_03_r720P.mp4")?;
+sheet::render(&chapters, "nwt_43_Joh_ASL_03_r720P.mp4", "John_3.png", &SheetOptions::default());
+```
+*/
+pub fn render(
+    root: &Root,
+    path: &Path,
+    output: &Path,
+    options: &SheetOptions,
+) -> Result<(), Error> {
+    let tiles: Vec<Tile> = tiles(root, options.frames_per_chapter);
+    if tiles.is_empty() {
+        return Err(Error::NoChapters);
+    }
+
+    let command =
+        command::run(Command::new("ffmpeg").args(sheet_args(&tiles, path, output, options)))
+            .map_err(Error::Run)?;
+
+    if !command.status.success() {
+        return Err(Error::Failure);
+    }
+    Ok(())
+}
+
+/// Returns the frames taken from each chapter, spread evenly over the chapter.
+fn tiles(root: &Root, frames_per_chapter: u32) -> Vec<Tile> {
+    let frames: u32 = frames_per_chapter.max(1);
+    let mut tiles: Vec<Tile> = Vec::new();
+
+    for chapter in root.chapters.iter() {
+        let (start, end) = chapter.times();
+        // The middle of each of the `frames` equal parts of the chapter.
+        let half_step: Timestamp = (end - start) / (2 * frames as i64);
+
+        for frame in 0..frames {
+            let time: Timestamp = start + half_step * (2 * frame as i64 + 1);
+            tiles.push(Tile {
+                time,
+                label: format!("{} {}", chapter.tags.title, clock(time.seconds())),
+            });
+        }
+    }
+
+    tiles
+}
+
+/**
+Returns the arguments passed to ffmpeg by [render].
+
+The file is read once: `select` keeps the first frame at or after the time of each tile, each frame is
+labelled by its number, and `tile` lays the frames out on the sheet.
+*/
+fn sheet_args(tiles: &[Tile], path: &Path, output: &Path, options: &SheetOptions) -> Vec<String> {
+    let select: Vec<String> = tiles
+        .iter()
+        .map(|tile| format!("gte(t,{0})*not(gte(prev_pts*TB,{0}))", tile.time))
+        .collect();
+
+    let mut filters: Vec<String> = vec![
+        format!("select='{}'", select.join("+")),
+        format!("scale={}:-2", options.width),
+    ];
+    for (i, tile) in tiles.iter().enumerate() {
+        let label = DrawText {
+            text: tile.label.clone(),
+            font: options.font.clone(),
+            size: (options.width / 20).max(10),
+            x: String::from("(w-text_w)/2"),
+            y: String::from("h-text_h-6"),
+            box_color: Some(String::from("black@0.6")),
+            enable: Some(format!("eq(n,{})", i)),
+            ..Default::default()
+        };
+        filters.push(label.filter());
+    }
+
+    let columns: u32 = options.columns.max(1);
+    let rows: u32 = (tiles.len() as u32).div_ceil(columns);
+    filters.push(format!(
+        "tile={}x{}:nb_frames={}:padding=4:margin=4",
+        columns,
+        rows,
+        tiles.len()
+    ));
+
+    vec![
+        String::from("-v"),
+        String::from(command::loglevel()),
+        String::from("-i"),
+        path.to_string_lossy().into_owned(),
+        String::from("-filter_complex"),
+        format!("[0:v]{}[sheet]", filters.join(",")),
+        String::from("-map"),
+        String::from("[sheet]"),
+        String::from("-frames:v"),
+        String::from("1"),
+        String::from("-y"),
+        output.to_string_lossy().into_owned(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffwrappers::probe::fixtures::init_struct_1;
+    use crate::timestamp::t;
+
+    #[test]
+    fn test_tiles() {
+        let tiles = tiles(&init_struct_1(), 2);
+        assert_eq!(tiles.len(), 8);
+        assert_eq!(tiles[0].time, t("204.7625"));
+        assert_eq!(tiles[0].label, "John 3:16 3:24");
+        assert_eq!(tiles[3].label, "John 3:17 3:57");
+    }
+
+    #[test]
+    fn test_sheet_args_layout() {
+        let tiles = tiles(&init_struct_1(), 3);
+        let options = SheetOptions {
+            columns: 4,
+            ..Default::default()
+        };
+        let args = sheet_args(
+            &tiles,
+            Path::new("in.mp4"),
+            Path::new("sheet.png"),
+            &options,
+        );
+        let filter: &String = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        assert!(filter.starts_with("[0:v]select='gte(t,202.374)"));
+        assert!(filter.ends_with("tile=4x3:nb_frames=12:padding=4:margin=4[sheet]"));
+        assert!(filter.contains(":enable='eq(n,11)'"));
+        assert_eq!(filter.matches("prev_pts").count(), 12);
+        assert_eq!(args.iter().filter(|a| *a == "-i").count(), 1);
+    }
+}
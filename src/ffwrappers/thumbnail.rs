@@ -0,0 +1,275 @@
+/*!
+Still image extraction
+
+Grabs a single frame out of a verse's span and saves it as an image, used as the poster frame of
+an extracted clip.
+*/
+
+use crate::ffwrappers::command;
+use crate::timestamp::Timestamp;
+use std::{io, path::Path, process::Command, str::FromStr};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("ffmpeg's exit status was FAILURE")]
+    Failure,
+    #[error("Unable to run ffmpeg: {0}")]
+    Run(#[source] io::Error),
+    #[error("The image format `{format:?}` is not supported")]
+    UnknownFormat { format: String },
+    #[error("The position `{position:?}` is not start, middle or a number of seconds")]
+    UnknownPosition { position: String },
+    #[error("The size `{size:?}` is not WIDTH or WIDTHxHEIGHT")]
+    UnknownSize { size: String },
+}
+
+/**
+Where inside a span the frame is taken from.
+
+A positive `Offset` is measured from the start of the span and a negative one from its end.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Position {
+    Start,
+    Middle,
+    Offset(Timestamp),
+}
+
+impl Position {
+    /// Returns the time of the frame inside the span from *start_time* to *end_time*.
+    pub fn resolve(&self, start_time: Timestamp, end_time: Timestamp) -> Timestamp {
+        let time: Timestamp = match self {
+            Position::Start => start_time,
+            Position::Middle => (start_time + end_time) / 2,
+            Position::Offset(o) if *o < Timestamp::ZERO => end_time + *o,
+            Position::Offset(o) => start_time + *o,
+        };
+        time.max(start_time).min(end_time)
+    }
+}
+
+impl FromStr for Position {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "start" => Ok(Position::Start),
+            "middle" => Ok(Position::Middle),
+            _ => match s.parse::<Timestamp>() {
+                Ok(o) => Ok(Position::Offset(o)),
+                Err(_) => Err(Error::UnknownPosition {
+                    position: s.to_string(),
+                }),
+            },
+        }
+    }
+}
+
+/// The image formats a frame can be saved as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl ImageFormat {
+    /// Returns the file extension used by the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Webp => "webp",
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(ImageFormat::Png),
+            "jpg" | "jpeg" => Ok(ImageFormat::Jpeg),
+            "webp" => Ok(ImageFormat::Webp),
+            _ => Err(Error::UnknownFormat {
+                format: s.to_string(),
+            }),
+        }
+    }
+}
+
+/**
+Parses a size given as `WIDTH` or `WIDTHxHEIGHT`.
+
+A missing height keeps the aspect ratio of the source.
+*/
+pub fn parse_size(size: &str) -> Result<(u32, Option<u32>), Error> {
+    let error = || Error::UnknownSize {
+        size: size.to_string(),
+    };
+    match size.split_once('x') {
+        Some((w, h)) => Ok((
+            w.parse().map_err(|_| error())?,
+            Some(h.parse().map_err(|_| error())?),
+        )),
+        None => Ok((size.parse().map_err(|_| error())?, None)),
+    }
+}
+
+/// Options controlling which frame is grabbed and how it is saved.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThumbnailOptions {
+    /// Where inside the span the frame is taken from.
+    pub position: Position,
+    /// The format the frame is saved as.
+    pub format: ImageFormat,
+    /// The width and optional height of the image. `None` keeps the size of the source.
+    pub size: Option<(u32, Option<u32>)>,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        ThumbnailOptions {
+            position: Position::Middle,
+            format: ImageFormat::Png,
+            size: None,
+        }
+    }
+}
+
+/**
+Saves a frame from inside the span *start_time* to *end_time* of the video as an image.
+
+The extension of `output` should match the image format, see [ImageFormat::extension].
+
+# Example
+```rust, ignore
+use ff::thumbnail;
+thumbnail::extract("197.597".parse()?, "226.259".parse()?, "nwt_43_Joh_ASL_03_r720P.mp4", "John_3-16.png", &ThumbnailOptions::default()); // Saves the frame in the middle of John 3:16.
+```
+*/
+pub fn extract(
+    start_time: Timestamp,
+    end_time: Timestamp,
+    path: &Path,
+    output: &Path,
+    options: &ThumbnailOptions,
+) -> Result<(), Error> {
+    let command = command::run(
+        Command::new("ffmpeg").args(thumbnail_args(start_time, end_time, path, output, options)),
+    )
+    .map_err(Error::Run)?;
+
+    if !command.status.success() {
+        return Err(Error::Failure);
+    }
+    Ok(())
+}
+
+/// Returns the arguments passed to ffmpeg by [extract].
+fn thumbnail_args(
+    start_time: Timestamp,
+    end_time: Timestamp,
+    path: &Path,
+    output: &Path,
+    options: &ThumbnailOptions,
+) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        String::from("-v"),
+        String::from(command::loglevel()),
+        String::from("-ss"),
+        options.position.resolve(start_time, end_time).to_string(),
+        String::from("-i"),
+        path.to_string_lossy().into_owned(),
+        String::from("-frames:v"),
+        String::from("1"),
+    ];
+
+    if let Some((width, height)) = options.size {
+        args.push(String::from("-vf"));
+        args.push(format!(
+            "scale={}:{}",
+            width,
+            height.map_or(-2, |h| h as i64)
+        ));
+    }
+
+    match options.format {
+        ImageFormat::Png => (),
+        ImageFormat::Jpeg => args.extend(vec![String::from("-q:v"), String::from("2")]),
+        ImageFormat::Webp => args.extend(vec![String::from("-c:v"), String::from("libwebp")]),
+    }
+
+    args.push(String::from("-y"));
+    args.push(output.to_string_lossy().into_owned());
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::t;
+
+    #[test]
+    fn test_position_resolve() {
+        let (start, end) = (t("10"), t("20"));
+        assert_eq!(Position::Start.resolve(start, end), t("10"));
+        assert_eq!(Position::Middle.resolve(start, end), t("15"));
+        assert_eq!(Position::Offset(t("2.5")).resolve(start, end), t("12.5"));
+        assert_eq!(Position::Offset(t("-1")).resolve(start, end), t("19"));
+        assert_eq!(Position::Offset(t("30")).resolve(start, end), t("20"));
+    }
+
+    #[test]
+    fn test_position_from_str() {
+        assert_eq!("middle".parse::<Position>().unwrap(), Position::Middle);
+        assert_eq!(
+            "-0.5".parse::<Position>().unwrap(),
+            Position::Offset(t("-0.5"))
+        );
+        assert!("end".parse::<Position>().is_err());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("320").unwrap(), (320, None));
+        assert_eq!(parse_size("320x180").unwrap(), (320, Some(180)));
+        assert!(parse_size("big").is_err());
+    }
+
+    #[test]
+    fn test_thumbnail_args() {
+        let options = ThumbnailOptions {
+            format: ImageFormat::Jpeg,
+            size: Some((320, None)),
+            ..Default::default()
+        };
+        assert_eq!(
+            thumbnail_args(
+                t("10"),
+                t("20.5"),
+                Path::new("in.mp4"),
+                Path::new("out.jpg"),
+                &options
+            ),
+            vec![
+                "-v",
+                "quiet",
+                "-ss",
+                "15.25",
+                "-i",
+                "in.mp4",
+                "-frames:v",
+                "1",
+                "-vf",
+                "scale=320:-2",
+                "-q:v",
+                "2",
+                "-y",
+                "out.jpg"
+            ]
+        );
+    }
+}
@@ -0,0 +1,27 @@
+/*!
+ScriptX extracts scriptures out of the American Sign Language version of the New World Translation.
+
+The [ffwrappers] read the chapters of a video with ffprobe, resolve verse references to their
+*start* and *end*, and cut them out with ffmpeg. The other modules work on the chapters once they
+are read, such as checking and repairing them, writing them as subtitles, timelines or chapter
+files, and reading verse texts and section headings. With the default `cli` feature the `scriptx`
+command line tool is built on top of them.
+
+## Features
+- `cli`, on by default, builds the command line tool with clap, indicatif and logging.
+- `log` logs the commands run, probe results and timings through the [log](https://docs.rs/log) crate.
+  It is enabled by `cli`; without it the library only depends on serde, serde_json, regex and thiserror.
+- `native-mp4` and `server` are reserved for backends that read mp4 files without ffprobe and serve
+  clips over HTTP. They don't enable anything yet.
+*/
+
+pub mod books;
+pub mod chapters;
+pub mod check;
+pub mod corpus;
+pub mod ffwrappers;
+pub mod repair;
+pub mod sections;
+pub mod subtitles;
+pub mod timeline;
+pub mod timestamp;
@@ -0,0 +1,104 @@
+/*!
+Logging
+
+Writes the messages logged by ScriptX to stderr and, when one is given, to a log file. How much is
+written follows the `--verbose` and `--quiet` flags: only errors when quiet, warnings by default,
+then the commands run and their timings, the probe results and ffmpeg's own output, and everything.
+
+The log file records at least the commands run and their timings, each line stamped with the
+seconds since ScriptX started.
+*/
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    sync::Mutex,
+    time::Instant,
+};
+
+/// Logs to stderr at one level, and to an optional file at another.
+struct Logger {
+    level: LevelFilter,
+    file: Option<(LevelFilter, Mutex<File>)>,
+    started: Instant,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let file: LevelFilter = self.file.as_ref().map_or(LevelFilter::Off, |f| f.0);
+        metadata.level() <= self.level.max(file)
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() <= self.level {
+            eprintln!("[{:<5}] {}", record.level(), record.args());
+        }
+        if let Some((level, file)) = &self.file {
+            if record.level() <= *level {
+                if let Ok(mut file) = file.lock() {
+                    let _ = writeln!(
+                        file,
+                        "{:>10.3} {:<5} {}: {}",
+                        self.started.elapsed().as_secs_f64(),
+                        record.level(),
+                        record.target(),
+                        record.args()
+                    );
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some((_, file)) = &self.file {
+            if let Ok(mut file) = file.lock() {
+                let _ = file.flush();
+            }
+        }
+    }
+}
+
+/// Returns the level to log at for the number of times `--verbose` was given, or `--quiet`.
+pub fn level(verbose: u64, quiet: bool) -> LevelFilter {
+    match (quiet, verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => LevelFilter::Warn,
+        (false, 1) => LevelFilter::Info,
+        (false, 2) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    }
+}
+
+/// Starts logging at `level`, also to the file at `path` when one is given. The file is overwritten.
+pub fn init(level: LevelFilter, path: Option<&Path>) -> io::Result<()> {
+    let file = match path {
+        Some(p) => Some((level.max(LevelFilter::Info), Mutex::new(File::create(p)?))),
+        None => None,
+    };
+    let max: LevelFilter = file.as_ref().map_or(level, |f| level.max(f.0));
+    let logger = Logger {
+        level,
+        file,
+        started: Instant::now(),
+    };
+
+    log::set_boxed_logger(Box::new(logger)).map_err(io::Error::other)?;
+    log::set_max_level(max);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level() {
+        assert_eq!(level(0, false), LevelFilter::Warn);
+        assert_eq!(level(1, false), LevelFilter::Info);
+        assert_eq!(level(2, false), LevelFilter::Debug);
+        assert_eq!(level(5, false), LevelFilter::Trace);
+        assert_eq!(level(2, true), LevelFilter::Error);
+    }
+}
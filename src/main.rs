@@ -2,17 +2,38 @@
 
 //! ScriptX is a command line tool designed to extract scriptures out of the American Sign Language version of the [New World Translation](https://www.jw.org/ase/library/bible/nwt/books/), NWT, Bible published by the [Watch Tower Bible and Tract Society of Pennsylvania](https://www.JW.org). It is useful when you need specific scripture(s) in a standalone file. A quick example would be if you need to splice scriptures into another video.
 
-mod ffwrappers;
+mod logger;
+mod report;
 use core::str;
 use indicatif::ProgressIterator;
-use std::path::Path;
+use log::info;
+use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::u8;
+use std::time::Instant;
 
-use clap::{crate_authors, crate_description, crate_version, App, Arg, ArgGroup, ArgMatches};
+use clap::{
+    crate_authors, crate_description, crate_version, App, AppSettings, Arg, ArgGroup, ArgMatches,
+    SubCommand,
+};
 
-use crate::ffwrappers::mpeg;
-use crate::ffwrappers::probe::Root;
+use report::Clip;
+use scriptx::chapters::{self, ChapterFormat};
+use scriptx::check;
+use scriptx::corpus::Corpus;
+use scriptx::ffwrappers::card::TitleCard;
+use scriptx::ffwrappers::drawtext::{self, DrawText, Placement};
+use scriptx::ffwrappers::format::{self, Container};
+use scriptx::ffwrappers::metadata;
+use scriptx::ffwrappers::mpeg::{self, CutOptions, Timestamps};
+use scriptx::ffwrappers::preview::{self, PreviewOptions};
+use scriptx::ffwrappers::probe::{self, Padding, Position, Root};
+use scriptx::ffwrappers::sheet::{self, SheetOptions};
+use scriptx::ffwrappers::thumbnail::{self, ThumbnailOptions};
+use scriptx::repair::{self, Fix};
+use scriptx::sections::{self, Section};
+use scriptx::subtitles::{self, SubtitleFormat};
+use scriptx::timeline::{self, TimelineFormat};
+use scriptx::timestamp::Timestamp;
 use scriptx_errors::ScriptxErrors;
 
 /// ScriptX - A Sign Language Bible verse slicer.
@@ -21,10 +42,10 @@ fn main() -> Result<(), ScriptxErrors> {
         .author(crate_authors!())
         .about(crate_description!())
         .version(crate_version!())
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
             Arg::with_name("verse")
-                .help("The verse to be extracted out. A single verse or a range of verses can be extracted. e.g. 2-5")
-                .short("v")
+                .help("The verse to be extracted out. A single verse or a range of verses can be extracted. e.g. 2-5. A comma separated list joins the verses into one file. e.g. 2,5-7")
                 .long("verse")
                 .takes_value(true),
         )
@@ -51,13 +72,601 @@ fn main() -> Result<(), ScriptxErrors> {
                 .long("all")
                 .takes_value(false),
         )
+        .arg(
+            Arg::with_name("group")
+                .help("Extracts the verses in clips of this many verses each, when extracting all scriptures.")
+                .long("group")
+                .takes_value(true)
+                .validator(is_count)
+                .requires("all"),
+        )
+        .arg(
+            Arg::with_name("groups")
+                .help("Extracts the verses in the given groups, when extracting all scriptures. e.g. 1-4,5-9,10-15")
+                .long("groups")
+                .takes_value(true)
+                .validator(is_groups)
+                .requires("all")
+                .conflicts_with("group"),
+        )
+        .arg(
+            Arg::with_name("sections")
+                .help("Extracts each section of the chapter, such as John 3:1-21, into a clip named after its heading.")
+                .long("sections")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("section_table")
+                .help("A table of sections to use instead of the built-in one, with one book, chapter, verses and heading per line separated by tabs. e.g. John<TAB>3<TAB>1-21<TAB>Jesus and Nicodemus")
+                .long("section-table")
+                .takes_value(true)
+                .requires("sections"),
+        )
+        .arg(
+            Arg::with_name("start")
+                .help("Where the cut starts, instead of a verse. Either a time as hh:mm:ss.mmm or seconds, or a verse and offset from its start such as 16+1.5s.")
+                .long("start")
+                .takes_value(true)
+                .validator(is_position)
+                .requires("end"),
+        )
+        .arg(
+            Arg::with_name("end")
+                .help("Where the cut ends. Either a time as hh:mm:ss.mmm or seconds, or a verse and offset from its end such as 18-0.5s.")
+                .long("end")
+                .takes_value(true)
+                .validator(is_position)
+                .requires("start"),
+        )
+        .arg(
+            Arg::with_name("pad_start")
+                .help("Seconds to add before the start of each cut. Negative values trim the cut instead.")
+                .long("pad-start")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .validator(is_seconds)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("pad_end")
+                .help("Seconds to add after the end of each cut. Negative values trim the cut instead.")
+                .long("pad-end")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .validator(is_seconds)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("offset")
+                .help("Seconds to shift every cut by, used when the file's chapters are out of sync.")
+                .long("offset")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .validator(is_seconds)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("clamp")
+                .help("Keeps the padding from reaching past the neighbouring verses.")
+                .long("clamp-neighbours")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("fade_in")
+                .help("Seconds to fade in at the start of the output. Re-encodes the output.")
+                .long("fade-in")
+                .takes_value(true)
+                .validator(is_seconds)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("fade_out")
+                .help("Seconds to fade out at the end of the output. Re-encodes the output.")
+                .long("fade-out")
+                .takes_value(true)
+                .validator(is_seconds)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("crossfade")
+                .help("Seconds to crossfade between verses joined into one output.")
+                .long("crossfade")
+                .takes_value(true)
+                .validator(is_seconds)
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .help("The container of the output file. Replaces the extension of the output path.")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["mp4", "mkv", "mov", "webm"]),
+        )
+        .arg(
+            Arg::with_name("preset")
+                .help("Transcodes the output with the named preset.")
+                .long("preset")
+                .takes_value(true)
+                .possible_values(&format::PRESETS),
+        )
+        .arg(
+            Arg::with_name("timestamps")
+                .help("Whether the output keeps the timestamps of the input file or is reset to start at zero. With preserve, the chapters of a stream copied clip start at the clip's first timestamp, the time it was cut at; with reset they start at zero.")
+                .long("timestamps")
+                .takes_value(true)
+                .possible_values(&["preserve", "reset"])
+                .default_value("preserve"),
+        )
+        .arg(
+            Arg::with_name("tag")
+                .help("Sets a tag written to the output from a template, e.g. title=\"{reference}\". May be repeated. Placeholders: {reference}, {book}, {chapter}, {verse}, {file}, {times} and {language}. An empty template removes the tag.")
+                .long("tag")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .validator(is_tag),
+        )
+        .arg(
+            Arg::with_name("language")
+                .help("The language code written to the output's tags. Defaults to the language in the input's file name.")
+                .long("language")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("thumbnail")
+                .help("Saves a thumbnail next to each extracted file, named after it.")
+                .long("thumbnail")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("thumbnail_at")
+                .help("Where inside the verse the thumbnail is taken: start, middle or seconds from the start. Negative seconds count from the end.")
+                .long("thumbnail-at")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .default_value("middle"),
+        )
+        .arg(
+            Arg::with_name("thumbnail_format")
+                .help("The image format of the thumbnails.")
+                .long("thumbnail-format")
+                .takes_value(true)
+                .possible_values(&["png", "jpg", "webp"])
+                .default_value("png"),
+        )
+        .arg(
+            Arg::with_name("thumbnail_size")
+                .help("The size of the thumbnails as WIDTH or WIDTHxHEIGHT. Defaults to the size of the video.")
+                .long("thumbnail-size")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("label")
+                .help("Burns a label with the reference into the video. Re-encodes the output.")
+                .long("label")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("label_text")
+                .help("The text of the label, a template using the same placeholders as --tag.")
+                .long("label-text")
+                .takes_value(true)
+                .default_value("{reference}"),
+        )
+        .arg(
+            Arg::with_name("label_position")
+                .help("Where the label is placed on the video.")
+                .long("label-position")
+                .takes_value(true)
+                .possible_values(&drawtext::PLACEMENTS)
+                .default_value("bottom-left"),
+        )
+        .arg(
+            Arg::with_name("label_font")
+                .help("The font file used for the label. Defaults to ffmpeg's default font.")
+                .long("label-font")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("label_size")
+                .help("The font size of the label in pixels.")
+                .long("label-size")
+                .takes_value(true)
+                .validator(is_count)
+                .default_value("36"),
+        )
+        .arg(
+            Arg::with_name("label_box")
+                .help("The color of a box drawn behind the label, e.g. black@0.5. No box is drawn by default.")
+                .long("label-box")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("label_duration")
+                .help("Shows the label for the first given seconds only. Defaults to the whole clip.")
+                .long("label-duration")
+                .takes_value(true)
+                .validator(is_seconds),
+        )
+        .arg(
+            Arg::with_name("title_card")
+                .help("Joins a title card with the reference in front of each clip. Re-encodes the output.")
+                .long("title-card")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("title_card_text")
+                .help("The text of the title card, a template using the same placeholders as --tag.")
+                .long("title-card-text")
+                .takes_value(true)
+                .default_value("{reference}"),
+        )
+        .arg(
+            Arg::with_name("title_card_background")
+                .help("The background of the title card, a color such as navy or #1a2b3c, or the path to an image such as card.png. A path is anything with a path separator or an image extension.")
+                .long("title-card-background")
+                .takes_value(true)
+                .default_value("black"),
+        )
+        .arg(
+            Arg::with_name("title_card_duration")
+                .help("How long the title card is shown in seconds.")
+                .long("title-card-duration")
+                .takes_value(true)
+                .validator(is_seconds)
+                .default_value("3"),
+        )
+        .arg(
+            Arg::with_name("title_card_font")
+                .help("The font file used for the title card. Defaults to ffmpeg's default font.")
+                .long("title-card-font")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("title_card_size")
+                .help("The font size of the title card in pixels.")
+                .long("title-card-size")
+                .takes_value(true)
+                .validator(is_count)
+                .default_value("72"),
+        )
+        .arg(
+            Arg::with_name("subtitles")
+                .help("Writes the verses of each clip as subtitle cues to a sidecar file next to it.")
+                .long("subtitles")
+                .takes_value(true)
+                .possible_values(&["srt", "vtt"]),
+        )
+        .arg(
+            Arg::with_name("mux_subtitles")
+                .help("Muxes the verses of each clip into it as a soft subtitle track, mov_text for mp4 and mov.")
+                .long("mux-subtitles")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("subtitle_text")
+                .help("Verse texts shown under each verse's title in the subtitles, read like --corpus, such as one reference<TAB>text line per verse. Its texts take the place of those of --corpus.")
+                .long("subtitle-text")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("corpus")
+                .help("A local Bible text, a .json or .csv file keyed by book, chapter and verse, or any other file of reference<TAB>text lines. The text of each verse is muxed into the clip as soft subtitles, timed to the verse.")
+                .long("corpus")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .help("Logs more of what ScriptX does: the commands run and their timings, then the probe results and ffmpeg's own output, then everything. Given up to three times, e.g. -vv.")
+                .short("v")
+                .long("verbose")
+                .multiple(true)
+                .global(true)
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .help("Logs only errors.")
+                .short("q")
+                .long("quiet")
+                .global(true)
+                .takes_value(false)
+                .conflicts_with("verbose"),
+        )
+        .arg(
+            Arg::with_name("json")
+                .help("Prints the result of each operation to stdout as a line of JSON, such as every clip saved with its references, times and outputs, or the error. Progress is written to stderr.")
+                .long("json")
+                .global(true)
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("log_file")
+                .help("Also writes the log to this file, always including the commands run and their timings.")
+                .long("log-file")
+                .global(true)
+                .takes_value(true),
+        )
         .group(ArgGroup::with_name("extraction_types")
-            .args(&["all", "verse"])
+            .args(&["all", "verse", "start", "sections"])
             .multiple(false)
             .required(true),
         )
+        .subcommand(
+            SubCommand::with_name("contact-sheet")
+                .about("Renders a contact sheet of frames from every verse in the file, labelled with the verse and its timestamp.")
+                .arg(
+                    Arg::with_name("file")
+                        .help("The input video file to process.")
+                        .short("f")
+                        .long("file")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("output_path")
+                        .help("The path were to save the contact sheet.")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .default_value("contact-sheet.png"),
+                )
+                .arg(
+                    Arg::with_name("columns")
+                        .help("The number of frames in each row.")
+                        .long("columns")
+                        .takes_value(true)
+                        .validator(is_count)
+                        .default_value("5"),
+                )
+                .arg(
+                    Arg::with_name("frames")
+                        .help("The number of frames taken from each verse.")
+                        .long("frames")
+                        .takes_value(true)
+                        .validator(is_count)
+                        .default_value("1"),
+                )
+                .arg(
+                    Arg::with_name("width")
+                        .help("The width of each frame in pixels.")
+                        .long("width")
+                        .takes_value(true)
+                        .validator(is_count)
+                        .default_value("320"),
+                )
+                .arg(
+                    Arg::with_name("font")
+                        .help("The font file used for the labels. Defaults to ffmpeg's default font.")
+                        .long("font")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("preview")
+                .about("Exports a verse or range of verses as an animated GIF or WebP preview.")
+                .arg(
+                    Arg::with_name("file")
+                        .help("The input video file to process.")
+                        .short("f")
+                        .long("file")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("verse")
+                        .help("The verse to be previewed. A single verse or a range of verses can be previewed. e.g. 2-5")
+                        .long("verse")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("output_path")
+                        .help("The path were to save the preview. The extension, .gif or .webp, sets the format.")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .default_value("preview.gif"),
+                )
+                .arg(
+                    Arg::with_name("fps")
+                        .help("The frame rate of the preview.")
+                        .long("fps")
+                        .takes_value(true)
+                        .validator(is_count)
+                        .default_value("10"),
+                )
+                .arg(
+                    Arg::with_name("width")
+                        .help("The width of the preview in pixels.")
+                        .long("width")
+                        .takes_value(true)
+                        .validator(is_count)
+                        .default_value("320"),
+                )
+                .arg(
+                    Arg::with_name("max_duration")
+                        .help("The longest the preview may run in seconds.")
+                        .long("max-duration")
+                        .takes_value(true)
+                        .validator(is_seconds),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("search")
+                .about("Searches a local Bible text for a phrase, listing the matching verses and the video files that contain them.")
+                .arg(
+                    Arg::with_name("phrase")
+                        .help("The phrase to search for, matched ignoring case and punctuation.")
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("corpus")
+                        .help("The local Bible text searched, a .json or .csv file keyed by book, chapter and verse.")
+                        .long("corpus")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("dir")
+                        .help("The directory of video files searched for the matching verses.")
+                        .short("d")
+                        .long("dir")
+                        .takes_value(true)
+                        .default_value("."),
+                )
+                .arg(
+                    Arg::with_name("extract")
+                        .help("Extracts every matching verse found in a video file.")
+                        .long("extract")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("output_path")
+                        .help("The directory were to save the extracted verses.")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .default_value("."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("timeline")
+                .about("Exports the verses of the file as markers on an editor timeline, a CMX3600 EDL, FCPXML or OpenTimelineIO file.")
+                .arg(
+                    Arg::with_name("file")
+                        .help("The input video file to process.")
+                        .short("f")
+                        .long("file")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("output_path")
+                        .help("The path were to save the timeline. The extension, .edl, .fcpxml or .otio, sets the format.")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .default_value("timeline.fcpxml"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("The format of the timeline. Defaults to the extension of the output.")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["edl", "fcpxml", "otio"]),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export-chapters")
+                .about("Exports the chapters of the file as an FFMETADATA file, a CUE sheet, CSV or m:ss Title lines for video descriptions.")
+                .arg(
+                    Arg::with_name("file")
+                        .help("The input video file to process.")
+                        .short("f")
+                        .long("file")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("output_path")
+                        .help("The path were to save the chapters. Printed when not given.")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .help("The format of the chapter list.")
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&chapters::FORMATS)
+                        .default_value("ffmetadata"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("check")
+                .about("Checks the chapters of the file for overlaps, gaps, duplicate or malformed titles, mismatched times, missing verses and chapters past the end of the file. Exits with an error when problems are found.")
+                .arg(
+                    Arg::with_name("file")
+                        .help("The input video file to process.")
+                        .short("f")
+                        .long("file")
+                        .takes_value(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("repair")
+                .about("Repairs the chapters of the file, sorting them, closing overlaps and gaps, normalising titles and recomputing ticks, then copies the file with the repaired chapters.")
+                .arg(
+                    Arg::with_name("file")
+                        .help("The input video file to process.")
+                        .short("f")
+                        .long("file")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("output_path")
+                        .help("The path were to save the repaired file. Defaults to the input with -repaired added to its name.")
+                        .short("o")
+                        .long("output")
+                        .takes_value(true)
+                        .conflicts_with("in_place"),
+                )
+                .arg(
+                    Arg::with_name("in_place")
+                        .help("Overwrites the input file with the repaired one.")
+                        .long("in-place")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("fix")
+                        .help("The fixes to apply, separated by commas. Defaults to all of them.")
+                        .long("fix")
+                        .takes_value(true)
+                        .multiple(true)
+                        .use_delimiter(true)
+                        .possible_values(&repair::FIXES),
+                )
+                .arg(
+                    Arg::with_name("dry_run")
+                        .help("Prints the changes without writing the file.")
+                        .long("dry-run")
+                        .takes_value(false),
+                ),
+        )
         .get_matches();
 
+    logger::init(
+        logger::level(m.occurrences_of("verbose"), m.is_present("quiet")),
+        m.value_of("log_file").map(Path::new),
+    )
+    .map_err(ScriptxErrors::LogError)?;
+
+    let result: Result<(), ScriptxErrors> = run(&m);
+    // The record `check` prints already lists the problems it returns as an error.
+    let reported: bool = matches!(
+        result,
+        Err(ScriptxErrors::CheckError(check::Error::Problems { .. }))
+    );
+    if let (true, false, Err(e)) = (m.is_present("json"), reported, &result) {
+        let (operation, sm) = match m.subcommand() {
+            (name, Some(sm)) => (name, sm),
+            _ => ("extract", &m),
+        };
+        report::print(
+            operation,
+            &serde_json::json!({ "file": sm.value_of("file"), "error": e.to_string() }),
+        );
+    }
+    result
+}
+
+/// Runs the subcommand, or extracts the verses, chosen on the command line.
+fn run(m: &ArgMatches) -> Result<(), ScriptxErrors> {
     // Todo: Find a better solution to this error message.
     let dependency_error_msg: &str = r#"ScriptX Error: 
         ffprobe and/or ffmpeg was not found on your system. Make sure it is installed.
@@ -84,70 +693,773 @@ fn main() -> Result<(), ScriptxErrors> {
         }
     };
 
+    match m.subcommand() {
+        ("contact-sheet", Some(sm)) => return contact_sheet(sm),
+        ("preview", Some(sm)) => return animated_preview(sm),
+        ("search", Some(sm)) => return search(m, sm),
+        ("timeline", Some(sm)) => return export_timeline(sm),
+        ("export-chapters", Some(sm)) => return export_chapters(sm),
+        ("check", Some(sm)) => return check_chapters(sm),
+        ("repair", Some(sm)) => return repair_chapters(sm),
+        _ => (),
+    }
+
     let path: &Path = Path::new(m.value_of("file").unwrap());
-    let output_path: &Path = Path::new(m.value_of("output_path").unwrap());
+    let mut output_path: PathBuf = PathBuf::from(m.value_of("output_path").unwrap());
+    let container: Option<Container> = match m.value_of("format") {
+        Some(f) => {
+            let container: Container = f.parse()?;
+            output_path.set_extension(container.extension());
+            Some(container)
+        }
+        None => output_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(|e| e.parse().ok()),
+    };
+    let output_path: &Path = output_path.as_path();
+    let settings: Settings = settings(m, path, container)?;
 
-    match m.is_present("all") {
-        true => {
-            match all_verses(path, output_path) {
+    match (m.is_present("all"), m.value_of("verse")) {
+        (true, _) if m.is_present("group") || m.is_present("groups") => {
+            let size: Option<usize> = m.value_of("group").map(|g| g.parse().unwrap());
+            match grouped_verses(path, size, m.value_of("groups"), output_path, &settings) {
                 Ok(_) => (),
                 Err(e) => return Err(e),
             };
         }
-        false => {
-            match some_verses(path, &m, output_path) {
+        (true, _) => {
+            match all_verses(path, output_path, &settings) {
+                Ok(_) => (),
+                Err(e) => return Err(e),
+            };
+        }
+        (false, Some(verse)) => {
+            match some_verses(path, verse, output_path, &settings) {
+                Ok(_) => (),
+                Err(e) => return Err(e),
+            };
+        }
+        (false, None) if m.is_present("sections") => {
+            match sectioned_verses(path, m.value_of("section_table"), output_path, &settings) {
+                Ok(_) => (),
+                Err(e) => return Err(e),
+            };
+        }
+        (false, None) => {
+            let start: Position = m.value_of("start").unwrap().parse()?;
+            let end: Position = m.value_of("end").unwrap().parse()?;
+            match some_span(path, &start, &end, output_path, &settings) {
                 Ok(_) => (),
                 Err(e) => return Err(e),
             };
         }
     };
 
-    fn all_verses(path: &Path, out_path: &Path) -> Result<(), ScriptxErrors> {
-        let chapters: Root = match Root::new(path).map_err(|_| ScriptxErrors::FileError) {
-            Ok(r) => r,
-            Err(e) => return Err(e),
-        };
-        let chapters_vec: Vec<(f64, f64)> = chapters.get_all_verses().unwrap();
-        let mut i: u8 = 1;
-
-        for scripture in chapters_vec.iter().progress() {
-            let (start_time, end_time) = *scripture;
-            let destination: &Path = out_path.parent().unwrap();
-            let file_name = out_path
-                .file_name()
-                .expect("Expected to find a file name.")
-                .to_str()
-                .unwrap();
-
-            mpeg::cut(
-                start_time,
-                end_time,
-                path,
-                format!("{}/{}-{}", destination.to_str().unwrap(), i, file_name).as_str(),
-            );
+    Ok(())
+}
+
+/// Returns the settings chosen on the command line for extracting verses from the file at `path` into the `container`.
+fn settings(
+    m: &ArgMatches,
+    path: &Path,
+    container: Option<Container>,
+) -> Result<Settings, ScriptxErrors> {
+    Ok(Settings {
+        json: m.is_present("json"),
+        padding: Padding {
+            start: m.value_of("pad_start").unwrap().parse().unwrap(),
+            end: m.value_of("pad_end").unwrap().parse().unwrap(),
+            offset: m.value_of("offset").unwrap().parse().unwrap(),
+            clamp_to_neighbours: m.is_present("clamp"),
+        },
+        cut: CutOptions {
+            fade_in: m.value_of("fade_in").unwrap().parse().unwrap(),
+            fade_out: m.value_of("fade_out").unwrap().parse().unwrap(),
+            crossfade: m.value_of("crossfade").unwrap().parse().unwrap(),
+            container,
+            preset: m.value_of("preset").map(|p| p.parse()).transpose()?,
+            timestamps: m.value_of("timestamps").unwrap().parse::<Timestamps>()?,
+            label: match m.is_present("label") {
+                true => {
+                    let (x, y) = m
+                        .value_of("label_position")
+                        .unwrap()
+                        .parse::<Placement>()?
+                        .position();
+                    Some(DrawText {
+                        text: m.value_of("label_text").unwrap().to_string(),
+                        font: m.value_of("label_font").map(String::from),
+                        size: m.value_of("label_size").unwrap().parse().unwrap(),
+                        x,
+                        y,
+                        box_color: m.value_of("label_box").map(String::from),
+                        enable: m.value_of("label_duration").map(|d| format!("lt(t,{})", d)),
+                        ..Default::default()
+                    })
+                }
+                false => None,
+            },
+            title_card: match m.is_present("title_card") {
+                true => Some(TitleCard {
+                    text: DrawText {
+                        text: m.value_of("title_card_text").unwrap().to_string(),
+                        font: m.value_of("title_card_font").map(String::from),
+                        size: m.value_of("title_card_size").unwrap().parse().unwrap(),
+                        x: String::from("(w-text_w)/2"),
+                        y: String::from("(h-text_h)/2"),
+                        ..Default::default()
+                    },
+                    background: m.value_of("title_card_background").unwrap().parse()?,
+                    duration: m.value_of("title_card_duration").unwrap().parse().unwrap(),
+                }),
+                false => None,
+            },
+            ..Default::default()
+        },
+        tags: TagOptions {
+            templates: m
+                .values_of("tag")
+                .map(|v| v.map(split_tag).collect())
+                .unwrap_or_default(),
+            language: m
+                .value_of("language")
+                .map(String::from)
+                .or_else(|| metadata::language_from_file_name(path))
+                .unwrap_or_default(),
+        },
+        thumbnail: match m.is_present("thumbnail") {
+            true => Some(ThumbnailOptions {
+                position: m.value_of("thumbnail_at").unwrap().parse()?,
+                format: m.value_of("thumbnail_format").unwrap().parse()?,
+                size: m
+                    .value_of("thumbnail_size")
+                    .map(thumbnail::parse_size)
+                    .transpose()?,
+            }),
+            false => None,
+        },
+        subtitles: SubtitleOptions {
+            sidecar: m.value_of("subtitles").map(|s| s.parse()).transpose()?,
+            mux: m.is_present("mux_subtitles") || m.is_present("corpus"),
+            corpus: read_corpus(m)?,
+        },
+    })
+}
+
+/// Extracts every verse of the file into its own numbered clip next to `out_path`.
+fn all_verses(path: &Path, out_path: &Path, settings: &Settings) -> Result<(), ScriptxErrors> {
+    let chapters: Root = Root::new(path)?;
+    let options = CutOptions {
+        no_audio: !chapters.has_audio(),
+        streams: chapters.streams.clone(),
+        ..settings.cut.clone()
+    };
+    options.check(&chapters.streams, false)?;
+    let chapters_vec: Vec<(Timestamp, Timestamp)> = chapters.get_all_verses().unwrap();
+
+    for (i, scripture) in (1u8..).zip(chapters_vec.iter().progress()) {
+        let (start_time, end_time) = chapters.pad(*scripture, &settings.padding)?;
+        let clip_path: PathBuf = labelled_path(out_path, &i.to_string());
+
+        let clip_chapters = metadata::clip_chapters(&chapters, &[(start_time, end_time)], 0.0);
+        let clip_options =
+            settings.clip_options(&options, clip_chapters, path, &[(start_time, end_time)]);
+        let started: Instant = Instant::now();
+        mpeg::cut(
+            start_time,
+            end_time,
+            path,
+            clip_path.to_str().unwrap(),
+            &clip_options,
+        )?;
+        let thumbnails =
+            settings.save_thumbnails(&chapters, &[(start_time, end_time)], path, &clip_path)?;
+        let subtitles = settings.save_subtitles(&clip_options, &clip_path)?;
+        settings.report(Clip {
+            thumbnails,
+            subtitles,
+            ..Clip::new(
+                path.to_path_buf(),
+                references(&clip_options),
+                &[(start_time, end_time)],
+                mpeg::output_length(&[(start_time, end_time)], &clip_options),
+                clip_path,
+                started,
+            )
+        });
+    }
+    Ok(())
+}
+
+/**
+Extracts the verses of the file in groups, each into its own clip next to `out_path` named after its
+verses, for example `1-4-output.m4v`.
+
+The groups are either the comma separated `ranges` or consecutive runs of `size` verses.
+*/
+fn grouped_verses(
+    path: &Path,
+    size: Option<usize>,
+    ranges: Option<&str>,
+    out_path: &Path,
+    settings: &Settings,
+) -> Result<(), ScriptxErrors> {
+    let chapters: Root = Root::new(path)?;
+    let groups: Vec<String> = match ranges {
+        Some(r) => r
+            .split(',')
+            .map(|g| g.split_whitespace().collect())
+            .collect(),
+        None => chapters.groups(size.unwrap_or(1))?,
+    };
+
+    for group in groups.iter().progress() {
+        let times: (Timestamp, Timestamp) =
+            chapters.pad(chapters.verse(group)?, &settings.padding)?;
+        let clip_path: PathBuf = labelled_path(out_path, group);
+        extract(path, &chapters, &[times], &clip_path, settings)?;
+    }
+    Ok(())
+}
+
+/**
+Extracts each section of the chapter into its own clip next to `out_path`, named after its heading,
+for example `1-Jesus_and_Nicodemus.m4v`.
+
+The sections are read from the `table` when one is given, otherwise the built-in table is used.
+*/
+fn sectioned_verses(
+    path: &Path,
+    table: Option<&str>,
+    out_path: &Path,
+    settings: &Settings,
+) -> Result<(), ScriptxErrors> {
+    let chapters: Root = Root::new(path)?;
+    let table: Vec<Section> = match table {
+        Some(t) => sections::read(Path::new(t))?,
+        None => sections::builtin(),
+    };
+
+    for (i, (section, times)) in
+        (1u32..).zip(sections::split(&chapters, &table)?.into_iter().progress())
+    {
+        let times: (Timestamp, Timestamp) = chapters.pad(times, &settings.padding)?;
+        let clip_path: PathBuf = labelled_path(out_path, &format!("{}-{}", i, section.file_stem()));
+        extract(path, &chapters, &[times], &clip_path, settings)?;
+    }
+    Ok(())
+}
+
+/// Extracts the `verse`, a verse, range or comma separated list, of the file into `output_path`.
+fn some_verses(
+    path: &Path,
+    verse: &str,
+    output_path: &Path,
+    settings: &Settings,
+) -> Result<(), ScriptxErrors> {
+    let chapters: Root = Root::new(path)?;
+    let segments: Vec<(Timestamp, Timestamp)> = chapters
+        .verses(verse)?
+        .into_iter()
+        .map(|times| chapters.pad(times, &settings.padding))
+        .collect::<Result<_, _>>()?;
+    extract(path, &chapters, &segments, output_path, settings)
+}
+
+/// Extracts the span of the file between the `start` and `end` positions into `output_path`.
+fn some_span(
+    path: &Path,
+    start: &Position,
+    end: &Position,
+    output_path: &Path,
+    settings: &Settings,
+) -> Result<(), ScriptxErrors> {
+    let chapters: Root = Root::new(path)?;
+    let span: (Timestamp, Timestamp) =
+        chapters.pad(chapters.span(start, end)?, &settings.padding)?;
+    extract(path, &chapters, &[span], output_path, settings)
+}
+
+/// Cuts the *segments* out of the file, joining them when there are several, into `output_path`.
+fn extract(
+    path: &Path,
+    chapters: &Root,
+    segments: &[(Timestamp, Timestamp)],
+    output_path: &Path,
+    settings: &Settings,
+) -> Result<(), ScriptxErrors> {
+    let options = CutOptions {
+        no_audio: !chapters.has_audio(),
+        streams: chapters.streams.clone(),
+        ..settings.cut.clone()
+    };
+    options.check(&chapters.streams, segments.len() > 1)?;
+    let clip_chapters = metadata::clip_chapters(chapters, segments, options.crossfade);
+    let options = settings.clip_options(&options, clip_chapters, path, segments);
+    let started: Instant = Instant::now();
+
+    match segments {
+        [(start_time, end_time)] => mpeg::cut(
+            *start_time,
+            *end_time,
+            path,
+            output_path.to_str().unwrap(),
+            &options,
+        )?,
+        _ => mpeg::join(segments, path, output_path.to_str().unwrap(), &options)?,
+    };
+
+    let thumbnails = settings.save_thumbnails(chapters, segments, path, output_path)?;
+    let subtitles = settings.save_subtitles(&options, output_path)?;
+    settings.report(Clip {
+        thumbnails,
+        subtitles,
+        ..Clip::new(
+            path.to_path_buf(),
+            references(&options),
+            segments,
+            mpeg::output_length(segments, &options),
+            output_path.to_path_buf(),
+            started,
+        )
+    });
+    Ok(())
+}
 
-            i += 1;
+/**
+Lists the verses of the corpus containing the phrase given to the `search` subcommand, and the video
+files of the directory containing each of them.
+
+With `--extract` each verse found is extracted from the first file containing it, named after its
+reference. The clips are cut with the extraction options given before the subcommand in `parent`,
+such as `--pad-start` or `--format`, and saved as `.m4v` unless a format is chosen.
+*/
+fn search(parent: &ArgMatches, m: &ArgMatches) -> Result<(), ScriptxErrors> {
+    let corpus: Corpus = Corpus::read(Path::new(m.value_of("corpus").unwrap()))?;
+    let phrase: &str = m.value_of("phrase").unwrap();
+    let output_dir: &Path = Path::new(m.value_of("output_path").unwrap());
+    let videos: Vec<(PathBuf, Root)> = index_videos(Path::new(m.value_of("dir").unwrap()))?;
+    let container: Option<Container> = parent.value_of("format").map(|f| f.parse()).transpose()?;
+
+    let json: bool = m.is_present("json");
+
+    let hits = corpus.search(phrase);
+    if hits.is_empty() && !json {
+        println!("No verses contain \"{}\".", phrase);
+        return Ok(());
+    }
+
+    for hit in hits {
+        let files: Vec<&(PathBuf, Root)> = videos
+            .iter()
+            .filter(|(_, root)| root.chapters.iter().any(|c| hit.matches(&c.tags.title)))
+            .collect();
+        match json {
+            true => report::print(
+                "search",
+                &serde_json::json!({
+                    "reference": hit.reference(),
+                    "text": hit.text,
+                    "files": files.iter().map(|(path, _)| path).collect::<Vec<_>>(),
+                }),
+            ),
+            false => {
+                println!("{}  {}", hit.reference(), hit.text);
+                if files.is_empty() {
+                    println!("    not found in any video file");
+                }
+                for (path, _) in files.iter() {
+                    println!("    {}", path.display());
+                }
+            }
+        }
+
+        if let (true, Some((path, _))) = (m.is_present("extract"), files.first()) {
+            let mut output_path: PathBuf = output_dir.join(format!(
+                "{}_{}-{}",
+                hit.book.replace(' ', "_"),
+                hit.chapter,
+                hit.verse
+            ));
+            output_path.set_extension(container.map_or("m4v", |c| c.extension()));
+            let settings = Settings {
+                json,
+                ..settings(parent, path, container)?
+            };
+            some_verses(path, &hit.verse.to_string(), &output_path, &settings)?;
         }
-        Ok(())
     }
+    Ok(())
+}
 
-    fn some_verses(path: &Path, m: &ArgMatches, output_path: &Path) -> Result<(), ScriptxErrors> {
-        let verse: &str = m.value_of("verse").unwrap();
+/// Returns the video files of the directory with their chapters, skipping files ffprobe can't read.
+fn index_videos(dir: &Path) -> Result<Vec<(PathBuf, Root)>, ScriptxErrors> {
+    let mut videos: Vec<(PathBuf, Root)> = Vec::new();
 
-        let chapters: Root = match Root::new(path).map_err(|_| ScriptxErrors::FileError) {
-            Ok(r) => r,
-            Err(e) => return Err(e),
-        };
+    for entry in std::fs::read_dir(dir).map_err(|_| ScriptxErrors::FileError)? {
+        let path: PathBuf = entry.map_err(|_| ScriptxErrors::FileError)?.path();
+        let extension: &str = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default();
+        if extension.parse::<Container>().is_err() {
+            continue;
+        }
+        if let Ok(root) = Root::new(&path) {
+            videos.push((path, root));
+        }
+    }
 
-        let (start_time, end_time) = chapters.verse(verse).unwrap();
+    videos.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(videos)
+}
 
-        mpeg::cut(start_time, end_time, path, output_path.to_str().unwrap());
-        Ok(())
+/// Exports the verses of the file given to the `timeline` subcommand as an editor timeline.
+fn export_timeline(m: &ArgMatches) -> Result<(), ScriptxErrors> {
+    let path: &Path = Path::new(m.value_of("file").unwrap());
+    let output_path: &Path = Path::new(m.value_of("output_path").unwrap());
+    let format: TimelineFormat = match m.value_of("format") {
+        Some(f) => f.parse()?,
+        None => output_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .parse()?,
+    };
+
+    let started: Instant = Instant::now();
+    let chapters: Root = Root::new(path)?;
+    let source: PathBuf = path.canonicalize().map_err(|_| ScriptxErrors::FileError)?;
+    timeline::write(&chapters, &source, format, output_path)?;
+    print_saved(m, "timeline", output_path, started);
+    Ok(())
+}
+
+/// Exports the chapters of the file given to the `export-chapters` subcommand, printing them without an output.
+fn export_chapters(m: &ArgMatches) -> Result<(), ScriptxErrors> {
+    let path: &Path = Path::new(m.value_of("file").unwrap());
+    let format: ChapterFormat = m.value_of("format").unwrap().parse()?;
+
+    let started: Instant = Instant::now();
+    let root: Root = Root::new(path)?;
+    let list: String = chapters::export(&root, path, format)?;
+    match (m.value_of("output_path"), m.is_present("json")) {
+        (Some(output), _) => {
+            std::fs::write(output, list).map_err(|_| ScriptxErrors::FileError)?;
+            print_saved(m, "export-chapters", Path::new(output), started);
+        }
+        (None, true) => report::print(
+            "export-chapters",
+            &serde_json::json!({ "file": path, "chapters": list }),
+        ),
+        (None, false) => print!("{}", list),
+    }
+    Ok(())
+}
+
+/// Prints the problems found with the chapters of the file given to the `check` subcommand.
+fn check_chapters(m: &ArgMatches) -> Result<(), ScriptxErrors> {
+    let path: &Path = Path::new(m.value_of("file").unwrap());
+    let root: Root = Root::new(path)?;
+    let problems: Vec<check::Problem> = check::check(&root);
+
+    match m.is_present("json") {
+        true => report::print(
+            "check",
+            &serde_json::json!({ "file": path, "problems": problems }),
+        ),
+        false if problems.is_empty() => {
+            println!("No problems found in the chapters of {}.", path.display())
+        }
+        false => {
+            for problem in problems.iter() {
+                println!("{}", problem.message);
+            }
+        }
+    }
+
+    match problems.len() {
+        0 => Ok(()),
+        count => Err(check::Error::Problems { count }.into()),
     }
+}
 
+/// Repairs the chapters of the file given to the `repair` subcommand, printing every change made.
+fn repair_chapters(m: &ArgMatches) -> Result<(), ScriptxErrors> {
+    let path: &Path = Path::new(m.value_of("file").unwrap());
+    let output: PathBuf = match (m.is_present("in_place"), m.value_of("output_path")) {
+        (true, _) => path.to_path_buf(),
+        (false, Some(o)) => PathBuf::from(o),
+        (false, None) => repair::repaired_path(path),
+    };
+    let fixes: Vec<Fix> = match m.values_of("fix") {
+        Some(f) => f.map(str::parse).collect::<Result<_, _>>()?,
+        None => repair::FIXES
+            .iter()
+            .map(|f| f.parse())
+            .collect::<Result<_, _>>()?,
+    };
+
+    let root: Root = Root::new(path)?;
+    let (repaired, changes) = repair::repair(&root, &fixes);
+    let json: bool = m.is_present("json");
+    let write: bool = !changes.is_empty() && !m.is_present("dry_run");
+    if !json {
+        if changes.is_empty() {
+            println!("Nothing to repair in the chapters of {}.", path.display());
+        }
+        for change in changes.iter() {
+            println!("{}", change);
+        }
+    }
+
+    if write {
+        repair::remux(&repaired, path, &output)?;
+        if !json {
+            println!("Saved the repaired file to {}.", output.display());
+        }
+    }
+    if json {
+        report::print(
+            "repair",
+            &serde_json::json!({
+                "file": path,
+                "changes": changes,
+                "output": if write { Some(&output) } else { None },
+            }),
+        );
+    }
+    Ok(())
+}
+
+/// Renders the contact sheet of the file given to the `contact-sheet` subcommand.
+fn contact_sheet(m: &ArgMatches) -> Result<(), ScriptxErrors> {
+    let path: &Path = Path::new(m.value_of("file").unwrap());
+    let output_path: &Path = Path::new(m.value_of("output_path").unwrap());
+    let options = SheetOptions {
+        columns: m.value_of("columns").unwrap().parse().unwrap(),
+        frames_per_chapter: m.value_of("frames").unwrap().parse().unwrap(),
+        width: m.value_of("width").unwrap().parse().unwrap(),
+        font: m.value_of("font").map(String::from),
+    };
+
+    let started: Instant = Instant::now();
+    let chapters: Root = Root::new(path)?;
+    sheet::render(&chapters, path, output_path, &options)?;
+    print_saved(m, "contact-sheet", output_path, started);
+    Ok(())
+}
+
+/// Exports the animated preview of the verses given to the `preview` subcommand.
+fn animated_preview(m: &ArgMatches) -> Result<(), ScriptxErrors> {
+    let path: &Path = Path::new(m.value_of("file").unwrap());
+    let output_path: &Path = Path::new(m.value_of("output_path").unwrap());
+    let options = PreviewOptions {
+        format: output_path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .parse()?,
+        fps: m.value_of("fps").unwrap().parse().unwrap(),
+        width: m.value_of("width").unwrap().parse().unwrap(),
+        max_duration: m
+            .value_of("max_duration")
+            .map(|d| Timestamp::from_seconds(d.parse().unwrap())),
+    };
+
+    let started: Instant = Instant::now();
+    let chapters: Root = Root::new(path)?;
+    let (start_time, end_time) = chapters.verse(m.value_of("verse").unwrap())?;
+    preview::render(start_time, end_time, path, output_path, &options)?;
+    if m.is_present("json") {
+        let segments: [(Timestamp, Timestamp); 1] = [(start_time, end_time)];
+        let references: Vec<String> = metadata::clip_chapters(&chapters, &segments, 0.0)
+            .into_iter()
+            .map(|c| c.title)
+            .collect();
+        report::print(
+            "preview",
+            &Clip::new(
+                path.to_path_buf(),
+                references,
+                &segments,
+                match options.max_duration {
+                    Some(max) => (end_time - start_time).min(max),
+                    None => end_time - start_time,
+                },
+                output_path.to_path_buf(),
+                started,
+            ),
+        );
+    }
     Ok(())
 }
 
+/// Prints the `output` saved by the `operation` from the file of the subcommand as JSON, with `--json`.
+fn print_saved(m: &ArgMatches, operation: &str, output: &Path, started: Instant) {
+    if m.is_present("json") {
+        report::print(
+            operation,
+            &serde_json::json!({
+                "file": m.value_of("file"),
+                "output": output,
+                "elapsed": started.elapsed().as_secs_f64(),
+            }),
+        );
+    }
+}
+
+/// The settings chosen on the command line for extracting verses.
+#[derive(Default)]
+struct Settings {
+    json: bool,
+    padding: Padding,
+    cut: CutOptions,
+    tags: TagOptions,
+    thumbnail: Option<ThumbnailOptions>,
+    subtitles: SubtitleOptions,
+}
+
+impl Settings {
+    /**
+    Returns the cut options for a clip made of the `chapters` cut out of `path` at the *segments*.
+
+    The tags and the texts of the label and title card are rendered from their templates. The chapters
+    are moved past the title card.
+    */
+    fn clip_options(
+        &self,
+        options: &CutOptions,
+        mut chapters: Vec<metadata::ClipChapter>,
+        path: &Path,
+        segments: &[(Timestamp, Timestamp)],
+    ) -> CutOptions {
+        let context = metadata::TagContext::new(&chapters, path, segments, &self.tags.language);
+        if let Some(card) = &options.title_card {
+            metadata::shift_chapters(
+                &mut chapters,
+                Timestamp::from_seconds(card.duration - options.crossfade.max(0.0)),
+            );
+        }
+        CutOptions {
+            tags: metadata::clip_tags(&context, &self.tags.templates),
+            label: options.label.as_ref().map(|l| DrawText {
+                text: context.render(&l.text),
+                ..l.clone()
+            }),
+            title_card: options.title_card.as_ref().map(|c| TitleCard {
+                text: DrawText {
+                    text: context.render(&c.text.text),
+                    ..c.text.clone()
+                },
+                ..c.clone()
+            }),
+            subtitles: match self.subtitles.mux {
+                true => subtitles::cues(&chapters, self.subtitles.corpus.as_ref()),
+                false => Vec::new(),
+            },
+            chapters,
+            ..options.clone()
+        }
+    }
+
+    /// Logs the clip saved, also printing it as JSON with `--json`.
+    fn report(&self, clip: Clip) {
+        info!("Saved {} in {:.2}s", clip.output.display(), clip.elapsed);
+        if self.json {
+            report::print("extract", &clip);
+        }
+    }
+
+    /// Saves the subtitles of a clip next to it, if a sidecar file was asked for, returning where.
+    fn save_subtitles(
+        &self,
+        options: &CutOptions,
+        clip_path: &Path,
+    ) -> Result<Option<PathBuf>, ScriptxErrors> {
+        match self.subtitles.sidecar {
+            Some(format) => {
+                let cues = subtitles::cues(&options.chapters, self.subtitles.corpus.as_ref());
+                let output: PathBuf = clip_path.with_extension(format.extension());
+                subtitles::write(&cues, format, &output)?;
+                Ok(Some(output))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /**
+    Saves the thumbnails of a clip next to it, if thumbnails were asked for, returning where.
+
+    A clip cut from one segment gets one thumbnail named after the clip, such as `John.jpg`. A clip
+    joined from several segments gets one for each segment, named after the clip and the verses of
+    the segment, such as `John-3.jpg` and `John-5-7.jpg`.
+    */
+    fn save_thumbnails(
+        &self,
+        chapters: &Root,
+        segments: &[(Timestamp, Timestamp)],
+        path: &Path,
+        clip_path: &Path,
+    ) -> Result<Vec<PathBuf>, ScriptxErrors> {
+        let options: &ThumbnailOptions = match &self.thumbnail {
+            Some(options) => options,
+            None => return Ok(Vec::new()),
+        };
+        let stem: String = clip_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let mut thumbnails: Vec<PathBuf> = Vec::new();
+        for (i, (start_time, end_time)) in segments.iter().enumerate() {
+            let name: String = match segments.len() {
+                1 => stem.clone(),
+                _ => format!(
+                    "{}-{}",
+                    stem,
+                    segment_verses(chapters, (*start_time, *end_time))
+                        .unwrap_or_else(|| (i + 1).to_string())
+                ),
+            };
+            let output: PathBuf = clip_path
+                .with_file_name(name)
+                .with_extension(options.format.extension());
+            thumbnail::extract(*start_time, *end_time, path, &output, options)?;
+            thumbnails.push(output);
+        }
+        Ok(thumbnails)
+    }
+}
+
+/// Returns the titles of the chapters in a clip, such as `John 3:16`.
+fn references(options: &CutOptions) -> Vec<String> {
+    options.chapters.iter().map(|c| c.title.clone()).collect()
+}
+
+/// Returns the path of a clip saved next to `out_path` and named after it with the `label` in front, such as `1-4-output.m4v`.
+fn labelled_path(out_path: &Path, label: &str) -> PathBuf {
+    let file_name: String = out_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    out_path.with_file_name(format!("{}-{}", label, file_name))
+}
+
+/// Returns the verses of the chapters in the segment, such as `16` or `5-7`.
+fn segment_verses(chapters: &Root, segment: (Timestamp, Timestamp)) -> Option<String> {
+    let verses: Vec<u32> = metadata::clip_chapters(chapters, &[segment], 0.0)
+        .iter()
+        .filter_map(|c| probe::split_title(&c.title).map(|t| t.2))
+        .collect();
+    match (verses.first(), verses.last()) {
+        (Some(first), Some(last)) if first == last => Some(first.to_string()),
+        (Some(first), Some(last)) => Some(format!("{}-{}", first, last)),
+        _ => None,
+    }
+}
+
 /// Returns a bool if the ffprobe is installed otherwise an error is returned.
 fn check_for_ffprobe() -> Result<bool, ScriptxErrors> {
     let ffprobe = Command::new("ffprobe").arg("-version").output();
@@ -168,9 +1480,100 @@ fn check_for_ffmpeg() -> Result<bool, ScriptxErrors> {
     }
 }
 
+/// The tag templates and language chosen on the command line.
+#[derive(Default)]
+struct TagOptions {
+    templates: Vec<(String, String)>,
+    language: String,
+}
+
+/// The subtitle outputs chosen on the command line.
+#[derive(Default)]
+struct SubtitleOptions {
+    sidecar: Option<SubtitleFormat>,
+    mux: bool,
+    corpus: Option<Corpus>,
+}
+
+/// Returns the verse texts given with `--corpus` and `--subtitle-text`, the texts of the latter taking the place of the former's.
+fn read_corpus(m: &ArgMatches) -> Result<Option<Corpus>, ScriptxErrors> {
+    let mut corpus: Option<Corpus> = m
+        .value_of("corpus")
+        .map(|c| Corpus::read(Path::new(c)))
+        .transpose()?;
+    if let Some(texts) = m.value_of("subtitle_text") {
+        corpus
+            .get_or_insert_with(Corpus::default)
+            .merge(Corpus::read(Path::new(texts))?);
+    }
+    Ok(corpus)
+}
+
+/// Splits a `key=template` command line value into its key and template.
+fn split_tag(value: &str) -> (String, String) {
+    let (key, template) = value.split_once('=').unwrap();
+    (key.trim().to_string(), template.to_string())
+}
+
+/// Validates that a command line value is a `key=template` pair.
+fn is_tag(value: String) -> Result<(), String> {
+    match value.split_once('=') {
+        Some((key, _)) if !key.trim().is_empty() => Ok(()),
+        _ => Err(format!("`{}` is not a key=template pair", value)),
+    }
+}
+
+/// Validates that a command line value is a whole number greater than zero.
+fn is_count(value: String) -> Result<(), String> {
+    match value.parse::<u32>() {
+        Ok(v) if v > 0 => Ok(()),
+        _ => Err(format!(
+            "`{}` is not a whole number greater than zero",
+            value
+        )),
+    }
+}
+
+/// Validates that a command line value is a comma separated list of verses or ranges of verses.
+fn is_groups(value: String) -> Result<(), String> {
+    let is_verse = |v: &str| !v.is_empty() && v.chars().all(|c| c.is_ascii_digit());
+    let valid: bool = value
+        .split(',')
+        .all(|group| match group.trim().split_once('-') {
+            Some((first, last)) => is_verse(first.trim()) && is_verse(last.trim()),
+            None => is_verse(group.trim()),
+        });
+
+    match valid {
+        true => Ok(()),
+        false => Err(format!(
+            "`{}` is not a comma separated list of verses such as 1-4,5-9",
+            value
+        )),
+    }
+}
+
+/// Validates that a command line value is a time, or a verse and an offset from it.
+fn is_position(value: String) -> Result<(), String> {
+    match value.parse::<Position>() {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Validates that a command line value is a number of seconds.
+fn is_seconds(value: String) -> Result<(), String> {
+    match value.parse::<f64>() {
+        Ok(v) if v.is_finite() => Ok(()),
+        _ => Err(format!("`{}` is not a number of seconds", value)),
+    }
+}
+
 /// Custom errors for the ScriptX project.
 pub mod scriptx_errors {
     use core::fmt;
+    use scriptx::ffwrappers::{card, drawtext, format, mpeg, preview, probe, sheet, thumbnail};
+    use scriptx::{chapters, check, corpus, repair, sections, subtitles, timeline};
 
     /// The various errors used within ScriptX.
     #[derive(Debug)]
@@ -179,10 +1582,132 @@ pub mod scriptx_errors {
         DependencyError,
         /// Errors dealing with file read or write.
         FileError,
+        /// Errors dealing with opening the log file.
+        LogError(std::io::Error),
+        /// Errors dealing with the chapter information of the file.
+        ChapterError(probe::Error),
+        /// Errors dealing with cutting the video with ffmpeg.
+        CutError(mpeg::Error),
+        /// Errors dealing with the container or codecs of the output.
+        FormatError(format::Error),
+        /// Errors dealing with saving thumbnails.
+        ThumbnailError(thumbnail::Error),
+        /// Errors dealing with rendering contact sheets.
+        SheetError(sheet::Error),
+        /// Errors dealing with exporting animated previews.
+        PreviewError(preview::Error),
+        /// Errors dealing with the title card.
+        TitleCardError(card::Error),
+        /// Errors dealing with the text burnt into the video.
+        LabelError(drawtext::Error),
+        /// Errors dealing with subtitles and verse texts.
+        SubtitleError(subtitles::Error),
+        /// Errors dealing with reading the text corpus.
+        CorpusError(corpus::Error),
+        /// Errors dealing with exporting editor timelines.
+        TimelineError(timeline::Error),
+        /// Errors dealing with exporting chapter lists.
+        ChapterListError(chapters::Error),
+        /// Errors dealing with problems found in the chapters.
+        CheckError(check::Error),
+        /// Errors dealing with repairing the chapters.
+        RepairError(repair::Error),
+        /// Errors dealing with the section table.
+        SectionError(sections::Error),
     }
 
     impl std::error::Error for ScriptxErrors {}
 
+    impl From<probe::Error> for ScriptxErrors {
+        fn from(e: probe::Error) -> Self {
+            ScriptxErrors::ChapterError(e)
+        }
+    }
+
+    impl From<mpeg::Error> for ScriptxErrors {
+        fn from(e: mpeg::Error) -> Self {
+            ScriptxErrors::CutError(e)
+        }
+    }
+
+    impl From<format::Error> for ScriptxErrors {
+        fn from(e: format::Error) -> Self {
+            ScriptxErrors::FormatError(e)
+        }
+    }
+
+    impl From<thumbnail::Error> for ScriptxErrors {
+        fn from(e: thumbnail::Error) -> Self {
+            ScriptxErrors::ThumbnailError(e)
+        }
+    }
+
+    impl From<sheet::Error> for ScriptxErrors {
+        fn from(e: sheet::Error) -> Self {
+            ScriptxErrors::SheetError(e)
+        }
+    }
+
+    impl From<preview::Error> for ScriptxErrors {
+        fn from(e: preview::Error) -> Self {
+            ScriptxErrors::PreviewError(e)
+        }
+    }
+
+    impl From<subtitles::Error> for ScriptxErrors {
+        fn from(e: subtitles::Error) -> Self {
+            ScriptxErrors::SubtitleError(e)
+        }
+    }
+
+    impl From<corpus::Error> for ScriptxErrors {
+        fn from(e: corpus::Error) -> Self {
+            ScriptxErrors::CorpusError(e)
+        }
+    }
+
+    impl From<timeline::Error> for ScriptxErrors {
+        fn from(e: timeline::Error) -> Self {
+            ScriptxErrors::TimelineError(e)
+        }
+    }
+
+    impl From<chapters::Error> for ScriptxErrors {
+        fn from(e: chapters::Error) -> Self {
+            ScriptxErrors::ChapterListError(e)
+        }
+    }
+
+    impl From<check::Error> for ScriptxErrors {
+        fn from(e: check::Error) -> Self {
+            ScriptxErrors::CheckError(e)
+        }
+    }
+
+    impl From<repair::Error> for ScriptxErrors {
+        fn from(e: repair::Error) -> Self {
+            ScriptxErrors::RepairError(e)
+        }
+    }
+
+    impl From<sections::Error> for ScriptxErrors {
+        fn from(e: sections::Error) -> Self {
+            ScriptxErrors::SectionError(e)
+        }
+    }
+
+    impl From<card::Error> for ScriptxErrors {
+        fn from(e: card::Error) -> Self {
+            ScriptxErrors::TitleCardError(e)
+        }
+    }
+
+    impl From<drawtext::Error> for ScriptxErrors {
+        fn from(e: drawtext::Error) -> Self {
+            ScriptxErrors::LabelError(e)
+        }
+    }
+
     impl std::fmt::Display for ScriptxErrors {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
             match self {
@@ -192,6 +1717,54 @@ pub mod scriptx_errors {
                 ScriptxErrors::FileError => {
                     write!(f, "LibraryError:")
                 }
+                ScriptxErrors::LogError(e) => {
+                    write!(f, "LogError: {}", e)
+                }
+                ScriptxErrors::ChapterError(e) => {
+                    write!(f, "ChapterError: {}", e)
+                }
+                ScriptxErrors::CutError(e) => {
+                    write!(f, "CutError: {}", e)
+                }
+                ScriptxErrors::FormatError(e) => {
+                    write!(f, "FormatError: {}", e)
+                }
+                ScriptxErrors::ThumbnailError(e) => {
+                    write!(f, "ThumbnailError: {}", e)
+                }
+                ScriptxErrors::SheetError(e) => {
+                    write!(f, "SheetError: {}", e)
+                }
+                ScriptxErrors::PreviewError(e) => {
+                    write!(f, "PreviewError: {}", e)
+                }
+                ScriptxErrors::TitleCardError(e) => {
+                    write!(f, "TitleCardError: {}", e)
+                }
+                ScriptxErrors::LabelError(e) => {
+                    write!(f, "LabelError: {}", e)
+                }
+                ScriptxErrors::SubtitleError(e) => {
+                    write!(f, "SubtitleError: {}", e)
+                }
+                ScriptxErrors::CorpusError(e) => {
+                    write!(f, "CorpusError: {}", e)
+                }
+                ScriptxErrors::TimelineError(e) => {
+                    write!(f, "TimelineError: {}", e)
+                }
+                ScriptxErrors::ChapterListError(e) => {
+                    write!(f, "ChapterListError: {}", e)
+                }
+                ScriptxErrors::CheckError(e) => {
+                    write!(f, "CheckError: {}", e)
+                }
+                ScriptxErrors::RepairError(e) => {
+                    write!(f, "RepairError: {}", e)
+                }
+                ScriptxErrors::SectionError(e) => {
+                    write!(f, "SectionError: {}", e)
+                }
             }
         }
     }
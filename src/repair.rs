@@ -0,0 +1,348 @@
+/*!
+Chapter repair
+
+Fixes the problems [check](crate::check) reports in the chapters of a file, then remuxes
+the file with the corrected chapters. The streams are copied untouched.
+
+The fixes work on the chapter times given by [Chapter::times], so a chapter is placed by the
+`start_time` and `end_time` ffprobe reports. The `start` and `end` ticks of every changed chapter
+are recomputed from those times in its `time_base`, so the written chapters always agree with
+themselves.
+*/
+
+use crate::chapters::{self, ChapterFormat};
+use crate::ffwrappers::command;
+use crate::ffwrappers::probe::{Chapter, Root};
+use crate::timestamp::Timestamp;
+use regex::Regex;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+    str::FromStr,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("ffmpeg's exit status was FAILURE")]
+    Failure,
+    #[error("The fix `{fix:?}` is not one of order, overlaps, gaps, titles or ticks")]
+    UnknownFix { fix: String },
+    #[error("Unable to run ffmpeg: {0}")]
+    Run(#[source] io::Error),
+    #[error("Unable to write the repaired chapters: {0}")]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Chapters(#[from] chapters::Error),
+}
+
+/// The fixes that can be applied to the chapters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fix {
+    /// Sorts the chapters by their start.
+    Order,
+    /// Ends each chapter where the next one starts when they overlap.
+    Overlaps,
+    /// Ends each chapter where the next one starts when there is a gap between them.
+    Gaps,
+    /// Rewrites titles such as `John  3 : 16` as `John 3:16`.
+    Titles,
+    /// Recomputes the ticks of every chapter from its times, not just of the changed ones.
+    Ticks,
+}
+
+/// The names of the fixes, as accepted on the command line.
+pub const FIXES: [&str; 5] = ["order", "overlaps", "gaps", "titles", "ticks"];
+
+impl FromStr for Fix {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "order" => Ok(Fix::Order),
+            "overlaps" => Ok(Fix::Overlaps),
+            "gaps" => Ok(Fix::Gaps),
+            "titles" => Ok(Fix::Titles),
+            "ticks" => Ok(Fix::Ticks),
+            _ => Err(Error::UnknownFix { fix: s.to_string() }),
+        }
+    }
+}
+
+/**
+Returns the chapters of `root` with the `fixes` applied, and a description of every change made.
+
+# Example
+```rust, ignore
+let chapters: Root = Root::new("nwt_43_Joh_ASL_03_r720P.mp4")?;
+let (repaired, changes) = repair::repair(&chapters, &[Fix::Gaps, Fix::Ticks]);
+```
+*/
+pub fn repair(root: &Root, fixes: &[Fix]) -> (Root, Vec<String>) {
+    let mut chapters: Vec<Chapter> = root.chapters.clone();
+    let mut changes: Vec<String> = Vec::new();
+    let mut changed: Vec<bool> = vec![fixes.contains(&Fix::Ticks); chapters.len()];
+
+    if fixes.contains(&Fix::Order) {
+        let before: Vec<i64> = chapters.iter().map(|c| c.id).collect();
+        chapters.sort_by_key(|c| c.times().0);
+        if chapters.iter().map(|c| c.id).ne(before) {
+            changes.push(String::from("Sorted the chapters by their start"));
+        }
+    }
+
+    for i in 1..chapters.len() {
+        let end: Timestamp = chapters[i - 1].times().1;
+        let next: Timestamp = chapters[i].times().0;
+        let fix: Option<Fix> = match next.cmp(&end) {
+            std::cmp::Ordering::Less => Some(Fix::Overlaps),
+            std::cmp::Ordering::Greater => Some(Fix::Gaps),
+            std::cmp::Ordering::Equal => None,
+        };
+
+        if let Some(f) = fix.filter(|f| fixes.contains(f)) {
+            let (chapter, following) = (&chapters[i - 1], &chapters[i]);
+            changes.push(format!(
+                "Chapter {} ({}): {} the end from {}s to {}s, where chapter {} starts",
+                chapter.id,
+                chapter.tags.title,
+                if f == Fix::Gaps { "moved" } else { "trimmed" },
+                end,
+                next,
+                following.id
+            ));
+            let chapter: &mut Chapter = &mut chapters[i - 1];
+            chapter.end_time = format!("{:.6}", next);
+            changed[i - 1] = true;
+        }
+    }
+
+    if fixes.contains(&Fix::Titles) {
+        for chapter in chapters.iter_mut() {
+            let title: String = normalise_title(&chapter.tags.title);
+            if title != chapter.tags.title {
+                changes.push(format!(
+                    "Chapter {}: renamed {:?} to {:?}",
+                    chapter.id, chapter.tags.title, title
+                ));
+                chapter.tags.title = title;
+            }
+        }
+    }
+
+    for (chapter, changed) in chapters.iter_mut().zip(changed) {
+        if changed && recompute_ticks(chapter) {
+            changes.push(format!(
+                "Chapter {} ({}): set the ticks to {}-{} in {}",
+                chapter.id, chapter.tags.title, chapter.start, chapter.end, chapter.time_base
+            ));
+        }
+    }
+
+    let repaired = Root {
+        chapters,
+        ..root.clone()
+    };
+    (repaired, changes)
+}
+
+/**
+Copies the file at `path` to `output` with the chapters of `root`, copying every stream untouched.
+
+When `output` is `path` the file is written next to it first and then moved over it.
+*/
+pub fn remux(root: &Root, path: &Path, output: &Path) -> Result<(), Error> {
+    let name: String = output
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let metadata: PathBuf =
+        std::env::temp_dir().join(format!("scriptx-{}-{}.txt", std::process::id(), name));
+    fs::write(
+        &metadata,
+        chapters::export(root, path, ChapterFormat::Ffmetadata)?,
+    )?;
+
+    let in_place: bool = output == path;
+    let target: PathBuf = match in_place {
+        true => output.with_file_name(format!(".scriptx-repair-{}", name)),
+        false => output.to_path_buf(),
+    };
+
+    let command = command::run(Command::new("ffmpeg").args(remux_args(path, &metadata, &target)));
+    let removed = fs::remove_file(metadata);
+    let command = command.map_err(Error::Run)?;
+    removed?;
+
+    if !command.status.success() {
+        if in_place {
+            let _ = fs::remove_file(&target);
+        }
+        return Err(Error::Failure);
+    }
+    if in_place {
+        fs::rename(target, output)?;
+    }
+    Ok(())
+}
+
+/// Returns the arguments passed to ffmpeg by [remux].
+fn remux_args(path: &Path, metadata: &Path, output: &Path) -> Vec<String> {
+    vec![
+        String::from("-v"),
+        String::from(command::loglevel()),
+        String::from("-i"),
+        path.to_string_lossy().into_owned(),
+        String::from("-f"),
+        String::from("ffmetadata"),
+        String::from("-i"),
+        metadata.to_string_lossy().into_owned(),
+        String::from("-map"),
+        String::from("0"),
+        String::from("-map_chapters"),
+        String::from("1"),
+        String::from("-c"),
+        String::from("copy"),
+        String::from("-y"),
+        output.to_string_lossy().into_owned(),
+    ]
+}
+
+/// Returns the path the repaired copy of `path` is written to by default, such as `John-repaired.mp4`.
+pub fn repaired_path(path: &Path) -> PathBuf {
+    let stem: String = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match path.extension() {
+        Some(e) => path.with_file_name(format!("{}-repaired.{}", stem, e.to_string_lossy())),
+        None => path.with_file_name(format!("{}-repaired", stem)),
+    }
+}
+
+/// Returns the title as `Book C:V`, collapsing runs of whitespace. Titles without a reference are only trimmed.
+fn normalise_title(title: &str) -> String {
+    let title: String = title.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let pattern = Regex::new(r"^(.+?)\s*(\d+)\s*:\s*(\d+)$").unwrap();
+
+    match pattern.captures(&title) {
+        Some(c) => format!("{} {}:{}", &c[1], &c[2], &c[3]),
+        None => title,
+    }
+}
+
+/**
+Recomputes the `start` and `end` ticks of the chapter from its times, returning `true` if they changed.
+
+A chapter without a usable time base is given one of milliseconds.
+*/
+fn recompute_ticks(chapter: &mut Chapter) -> bool {
+    let (start, end) = chapter.times();
+    if Timestamp::from_ticks(1, &chapter.time_base).is_err() {
+        chapter.time_base = String::from("1/1000");
+    }
+    let start: i64 = start.to_ticks(&chapter.time_base).unwrap_or_default();
+    let end: i64 = end.to_ticks(&chapter.time_base).unwrap_or_default();
+
+    let changed: bool = (start, end) != (chapter.start, chapter.end);
+    chapter.start = start;
+    chapter.end = end;
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check;
+    use crate::ffwrappers::probe::fixtures::{chapter, root};
+
+    #[test]
+    fn test_normalise_title() {
+        assert_eq!(normalise_title("  John  3 : 16 "), "John 3:16");
+        assert_eq!(normalise_title("1 John 4:8"), "1 John 4:8");
+        assert_eq!(normalise_title(" Intro "), "Intro");
+    }
+
+    #[test]
+    fn test_repair() {
+        let mut mismatched = chapter(17, "226.259", "241.908", "John 3:17");
+        mismatched.start = 197597;
+        let root = root(vec![
+            chapter(18, "250", "260", "John 3:18"),
+            chapter(16, "197.597", "230", "John  3:16"),
+            mismatched,
+        ]);
+
+        let (repaired, changes) = repair(&root, &[Fix::Order, Fix::Overlaps, Fix::Titles]);
+        let chapters: Vec<(i64, i64, &str)> = repaired
+            .chapters
+            .iter()
+            .map(|c| (c.start, c.end, c.tags.title.as_str()))
+            .collect();
+        assert_eq!(
+            chapters,
+            vec![
+                (197597, 226259, "John 3:16"),
+                (197597, 241908, "John 3:17"),
+                (250000, 260000, "John 3:18"),
+            ]
+        );
+        assert_eq!(changes.len(), 4);
+
+        let (repaired, _) = repair(&root, &[Fix::Order, Fix::Gaps, Fix::Ticks]);
+        assert_eq!(repaired.chapters[1].start, 226259);
+        assert_eq!(repaired.chapters[1].end, 250000);
+        assert_eq!(repaired.chapters[1].end_time, "250.000000");
+    }
+
+    #[test]
+    fn test_repair_ticks_follow_times() {
+        let mut mismatched = chapter(17, "226.259", "241.908", "John 3:17");
+        mismatched.start = 197597;
+        let root = root(vec![
+            chapter(16, "197.597", "226.259", "John 3:16"),
+            mismatched,
+        ]);
+        assert_eq!(check::check(&root).len(), 1);
+
+        let (repaired, _) = repair(&root, &[Fix::Ticks]);
+        assert!(check::check(&repaired).is_empty());
+        let times = |r: &Root| r.chapters.iter().map(Chapter::times).collect::<Vec<_>>();
+        assert_eq!(times(&repaired), times(&root));
+    }
+
+    #[test]
+    fn test_repaired_path() {
+        assert_eq!(
+            repaired_path(Path::new("videos/John.mp4")),
+            PathBuf::from("videos/John-repaired.mp4")
+        );
+    }
+
+    #[test]
+    fn test_remux_args() {
+        let args = remux_args(
+            Path::new("John.m4v"),
+            Path::new("chapters.txt"),
+            Path::new("John-repaired.m4v"),
+        );
+        assert_eq!(
+            args[4..],
+            [
+                "-f",
+                "ffmetadata",
+                "-i",
+                "chapters.txt",
+                "-map",
+                "0",
+                "-map_chapters",
+                "1",
+                "-c",
+                "copy",
+                "-y",
+                "John-repaired.m4v"
+            ]
+        );
+    }
+}
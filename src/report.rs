@@ -0,0 +1,136 @@
+/*!
+JSON results
+
+With `--json` every operation prints its result to stdout as one line of JSON, an object whose
+`operation` names the subcommand, or `extract` for verses. Extracting prints a line for every clip
+as soon as it is saved, so a pipeline can follow along, and a failure prints a line with the `error`.
+Progress bars and log messages are written to stderr, leaving stdout to the results.
+*/
+
+use scriptx::timestamp::Timestamp;
+use serde::Serialize;
+use serde_json::Value;
+use std::{path::PathBuf, time::Instant};
+
+/// A clip saved by ScriptX.
+#[derive(Debug, Serialize)]
+pub struct Clip {
+    /// The file the clip was cut from.
+    pub file: PathBuf,
+    /// The titles of the chapters in the clip, such as `John 3:16`.
+    pub references: Vec<String>,
+    /// The parts of the file cut into the clip, in seconds.
+    pub segments: Vec<Span>,
+    /// The clip.
+    pub output: PathBuf,
+    /// The thumbnails saved with the clip, one for each segment.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub thumbnails: Vec<PathBuf>,
+    /// The subtitles saved with the clip.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtitles: Option<PathBuf>,
+    /// The length of the clip in seconds, including any title card and less any crossfades.
+    pub duration: f64,
+    /// The seconds it took to save the clip.
+    pub elapsed: f64,
+}
+
+/// The *start* and *end* of a segment in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Span {
+    /// The start of the segment.
+    pub start: f64,
+    /// The end of the segment.
+    pub end: f64,
+}
+
+impl Clip {
+    /// Returns the clip of the given `duration` saved to `output` from the *segments* of `file`, started at `started`.
+    pub fn new(
+        file: PathBuf,
+        references: Vec<String>,
+        segments: &[(Timestamp, Timestamp)],
+        duration: Timestamp,
+        output: PathBuf,
+        started: Instant,
+    ) -> Clip {
+        Clip {
+            file,
+            references,
+            segments: segments
+                .iter()
+                .map(|(start, end)| Span {
+                    start: start.seconds(),
+                    end: end.seconds(),
+                })
+                .collect(),
+            output,
+            thumbnails: Vec::new(),
+            subtitles: None,
+            duration: duration.seconds(),
+            elapsed: started.elapsed().as_secs_f64(),
+        }
+    }
+}
+
+/// Returns the `result` of the `operation` as a JSON object, with the operation added to its fields.
+fn line<T: Serialize>(operation: &str, result: &T) -> Value {
+    let mut line = serde_json::json!({ "operation": operation });
+    match serde_json::to_value(result) {
+        Ok(Value::Object(fields)) => line.as_object_mut().unwrap().extend(fields),
+        Ok(value) => line["result"] = value,
+        Err(e) => line["error"] = Value::String(e.to_string()),
+    }
+    line
+}
+
+/// Prints the `result` of the `operation` as one line of JSON.
+pub fn print<T: Serialize>(operation: &str, result: &T) {
+    println!("{}", line(operation, result));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clip() {
+        let clip = Clip::new(
+            PathBuf::from("John.mp4"),
+            vec![String::from("John 3:16"), String::from("John 3:18")],
+            &[
+                ("197.597".parse().unwrap(), "226.259".parse().unwrap()),
+                ("241.908".parse().unwrap(), "250".parse().unwrap()),
+            ],
+            "35.754".parse().unwrap(),
+            PathBuf::from("output.m4v"),
+            Instant::now(),
+        );
+        assert_eq!(
+            clip.segments[1],
+            Span {
+                start: 241.908,
+                end: 250.0
+            }
+        );
+        assert!((clip.duration - 35.754).abs() < 1e-9);
+
+        let line = line("extract", &clip);
+        assert_eq!(line["operation"], "extract");
+        assert_eq!(line["output"], "output.m4v");
+        assert_eq!(line["references"][1], "John 3:18");
+        assert!(line.get("thumbnails").is_none());
+    }
+
+    #[test]
+    fn test_line_error() {
+        let line = line(
+            "check",
+            &serde_json::json!({ "file": "John.mp4", "error": "CheckError" }),
+        );
+        assert_eq!(
+            line.to_string(),
+            r#"{"error":"CheckError","file":"John.mp4","operation":"check"}"#
+        );
+    }
+}
@@ -0,0 +1,294 @@
+/*!
+Section headings
+
+Chapters divide into sections, or pericopes, such as John 3:1-21 and 3:22-36. This module reads a
+table of sections and works out where each section of a chapter video starts and ends from the
+chapters of its verses, so the video can be split into one clip per section.
+
+A table has one `book<TAB>chapter<TAB>verses<TAB>heading` line per section, for example
+`John<TAB>3<TAB>1-21<TAB>Jesus and Nicodemus`. Blank lines and lines starting with `#` are skipped. A small
+table is built in, and any other can be read from a file.
+*/
+
+use crate::books::book_key;
+use crate::ffwrappers::probe::{self, Root};
+use crate::timestamp::Timestamp;
+use std::{fs, io, path::Path};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Line {line} of the section table is not a book, chapter, verses and heading separated by tabs")]
+    Malformed { line: usize },
+    #[error("Unable to read the section table: {0}")]
+    Io(#[from] io::Error),
+    #[error("The section table has no sections for {reference}")]
+    NoSections { reference: String },
+}
+
+/// The built-in section table.
+const BUILTIN: &str = "\
+# Matthew
+Matthew	5	1-12	The Sermon on the Mount
+Matthew	5	13-16	Salt and light
+Matthew	5	17-48	Jesus fulfills the Law
+Matthew	6	1-4	Giving in secret
+Matthew	6	5-15	The model prayer
+Matthew	6	16-18	Fasting
+Matthew	6	19-34	Treasures in heaven
+Matthew	7	1-6	Stop judging
+Matthew	7	7-12	Keep on asking
+Matthew	7	13-29	The narrow gate
+# John
+John	1	1-18	The Word became flesh
+John	1	19-34	The witness of John the Baptist
+John	1	35-51	The first disciples
+John	2	1-12	The wedding at Cana
+John	2	13-25	Jesus cleanses the temple
+John	3	1-21	Jesus and Nicodemus
+John	3	22-36	John the Baptist's last witness
+John	4	1-42	Jesus and the Samaritan woman
+John	4	43-54	Jesus heals an official's son
+";
+
+/// A section with its *start* and *end* in the file.
+pub type SectionSpan<'a> = (&'a Section, (Timestamp, Timestamp));
+
+/// A section of a chapter.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Section {
+    /// The book as it is named in the table.
+    pub book: String,
+    /// The chapter of the book.
+    pub chapter: u32,
+    /// The first verse of the section.
+    pub first: u32,
+    /// The last verse of the section.
+    pub last: u32,
+    /// The heading of the section.
+    pub heading: String,
+}
+
+impl Section {
+    /// Returns the heading as a file name, with runs of anything but letters and digits replaced by `_`.
+    pub fn file_stem(&self) -> String {
+        self.heading
+            .split(|c: char| !c.is_alphanumeric() && c != '\'')
+            .filter(|w| !w.is_empty())
+            .map(|w| w.replace('\'', ""))
+            .collect::<Vec<String>>()
+            .join("_")
+    }
+
+    /**
+    Returns `true` when the section belongs to the book and chapter.
+
+    Books are matched by their [book_key], so a known abbreviation matches the full name, such as
+    `Matt.` for `Matthew`, while `Phil.` matches Philippians and not Philemon.
+    */
+    fn covers(&self, book: &str, chapter: u32) -> bool {
+        let key: String = book_key(&self.book);
+        self.chapter == chapter && !key.is_empty() && key == book_key(book)
+    }
+}
+
+/// Parses a section table.
+pub fn parse(file: &str) -> Result<Vec<Section>, Error> {
+    let mut sections: Vec<Section> = Vec::new();
+
+    for (i, line) in file.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let malformed = || Error::Malformed { line: i + 1 };
+        let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+        let (book, chapter, verses, heading) = match fields.as_slice() {
+            [book, chapter, verses, heading] if !book.is_empty() && !heading.is_empty() => {
+                (*book, *chapter, *verses, *heading)
+            }
+            _ => return Err(malformed()),
+        };
+        let (first, last) = verses.split_once('-').unwrap_or((verses, verses));
+        let (first, last): (u32, u32) = match (first.trim().parse(), last.trim().parse()) {
+            (Ok(f), Ok(l)) if f <= l => (f, l),
+            _ => return Err(malformed()),
+        };
+
+        sections.push(Section {
+            book: book.to_string(),
+            chapter: chapter.parse().map_err(|_| malformed())?,
+            first,
+            last,
+            heading: heading.to_string(),
+        });
+    }
+
+    Ok(sections)
+}
+
+/// Reads the section table at `path`, see [parse].
+pub fn read(path: &Path) -> Result<Vec<Section>, Error> {
+    parse(&fs::read_to_string(path)?)
+}
+
+/// Returns the built-in section table.
+pub fn builtin() -> Vec<Section> {
+    parse(BUILTIN).expect("The built-in section table is malformed.")
+}
+
+/**
+Returns the sections of the chapter in `root` with the *start* and *end* of each, in the order of the table.
+
+A section starts where the first of its verses in the file starts and ends where the last of them
+ends. Sections without any of their verses in the file are left out.
+
+# Example
+```rust, ignore
+let chapters: Root = Root::new("nwt_43_Joh_ASL_03_r720P.mp4")?;
+for (section, (start, end)) in sections::split(&chapters, &sections::builtin())? {
+    println!("{} {}-{}", section.heading, start, end);
+}
+```
+*/
+pub fn split<'a>(root: &Root, sections: &'a [Section]) -> Result<Vec<SectionSpan<'a>>, Error> {
+    let verses: Vec<(&str, u32, u32, (Timestamp, Timestamp))> = root
+        .chapters
+        .iter()
+        .filter_map(|c| {
+            let (book, chapter, verse) = probe::split_title(&c.tags.title)?;
+            Some((book, chapter, verse, c.times()))
+        })
+        .collect();
+    let (book, chapter) = match verses.last() {
+        Some((book, chapter, _, _)) => (*book, *chapter),
+        None => {
+            return Err(Error::NoSections {
+                reference: String::from("a file without verses"),
+            })
+        }
+    };
+
+    let mut spans: Vec<SectionSpan> = Vec::new();
+    for section in sections.iter().filter(|s| s.covers(book, chapter)) {
+        let times: Vec<(Timestamp, Timestamp)> = verses
+            .iter()
+            .filter(|(b, c, v, _)| {
+                section.covers(b, *c) && (section.first..=section.last).contains(v)
+            })
+            .map(|(_, _, _, times)| *times)
+            .collect();
+        let start: Option<Timestamp> = times.iter().map(|t| t.0).min();
+        let end: Option<Timestamp> = times.iter().map(|t| t.1).max();
+        if let (Some(start), Some(end)) = (start, end) {
+            spans.push((section, (start, end)));
+        }
+    }
+
+    match spans.is_empty() {
+        true => Err(Error::NoSections {
+            reference: format!("{} {}", book, chapter),
+        }),
+        false => Ok(spans),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffwrappers::probe::fixtures::{chapter, root};
+    use crate::timestamp::t;
+
+    #[test]
+    fn test_parse() {
+        let sections =
+            parse("# John\nJohn\t3\t1-21\tJesus and Nicodemus\n\n1 John\t4\t8\tGod is love\n")
+                .unwrap();
+        assert_eq!(
+            sections[1],
+            Section {
+                book: String::from("1 John"),
+                chapter: 4,
+                first: 8,
+                last: 8,
+                heading: String::from("God is love"),
+            }
+        );
+        assert_eq!((sections[0].first, sections[0].last), (1, 21));
+        assert!(matches!(
+            parse("John\t3\t21-1\tBackwards").unwrap_err(),
+            Error::Malformed { line: 1 }
+        ));
+        assert!(parse("John 3:1-21\tJesus and Nicodemus").is_err());
+    }
+
+    #[test]
+    fn test_builtin() {
+        let sections = builtin();
+        assert!(sections
+            .iter()
+            .any(|s| s.covers("John", 3) && (s.first, s.last) == (22, 36)));
+    }
+
+    #[test]
+    fn test_file_stem() {
+        let section = Section {
+            heading: String::from("John the Baptist's last witness"),
+            ..Default::default()
+        };
+        assert_eq!(section.file_stem(), "John_the_Baptists_last_witness");
+    }
+
+    #[test]
+    fn test_split() {
+        let root = root(vec![
+            chapter(1, "0", "5", "John 3:1"),
+            chapter(2, "5", "197.597", "John 3:2"),
+            chapter(22, "197.597", "226.259", "John 3:22"),
+            chapter(23, "226.259", "241.908", "John 3:23"),
+        ]);
+        let sections = parse(
+            "John\t3\t1-21\tJesus and Nicodemus\n\
+             John\t3\t22-36\tJohn the Baptist's last witness\n\
+             John\t4\t1-42\tJesus and the Samaritan woman\n",
+        )
+        .unwrap();
+        let spans = split(&root, &sections).unwrap();
+        let spans: Vec<(&str, (Timestamp, Timestamp))> = spans
+            .iter()
+            .map(|(s, times)| (s.heading.as_str(), *times))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                ("Jesus and Nicodemus", (t("0"), t("197.597"))),
+                (
+                    "John the Baptist's last witness",
+                    (t("197.597"), t("241.908"))
+                ),
+            ]
+        );
+
+        assert!(split(&root, &sections[2..]).is_err());
+    }
+
+    #[test]
+    fn test_covers_abbreviation() {
+        let section = Section {
+            book: String::from("Matthew"),
+            chapter: 5,
+            ..Default::default()
+        };
+        assert!(section.covers("Matt.", 5));
+        assert!(!section.covers("Mat", 5));
+        assert!(!section.covers("Mark", 5));
+        assert!(!section.covers("Matthew", 6));
+
+        let philemon = Section {
+            book: String::from("Philemon"),
+            chapter: 1,
+            ..Default::default()
+        };
+        assert!(!philemon.covers("Phil.", 1));
+        assert!(philemon.covers("Philem.", 1));
+    }
+}
@@ -0,0 +1,193 @@
+/*!
+Subtitle cues for extracted clips
+
+Turns the chapters of a clip into [SubRip](https://en.wikipedia.org/wiki/SubRip) or
+[WebVTT](https://www.w3.org/TR/webvtt1/) cues, one cue per verse, so the reference can be shown by
+the player. The cues are written as a sidecar file next to the clip or muxed into it as a soft
+subtitle track.
+
+The text of each verse can be shown under its title, taken from a [Corpus].
+*/
+
+use crate::corpus::Corpus;
+use crate::ffwrappers::metadata::ClipChapter;
+use crate::timestamp::Timestamp;
+use std::{fs, io, path::Path, str::FromStr};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The subtitle format `{format:?}` is not supported")]
+    UnknownFormat { format: String },
+    #[error("Unable to read or write the subtitles: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// The subtitle formats cues can be written as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    /// Returns the file extension used by the format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+        }
+    }
+
+    /// Returns the cues formatted as a file of this format.
+    pub fn render(&self, cues: &[Cue]) -> String {
+        match self {
+            SubtitleFormat::Srt => srt(cues),
+            SubtitleFormat::Vtt => webvtt(cues),
+        }
+    }
+}
+
+impl FromStr for SubtitleFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "srt" => Ok(SubtitleFormat::Srt),
+            "vtt" | "webvtt" => Ok(SubtitleFormat::Vtt),
+            _ => Err(Error::UnknownFormat {
+                format: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// A subtitle shown from *start* to *end*, timed from the start of the clip.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Cue {
+    /// When the cue is shown.
+    pub start: Timestamp,
+    /// When the cue is hidden.
+    pub end: Timestamp,
+    /// The text of the cue, which may span several lines.
+    pub text: String,
+}
+
+/**
+Returns one cue per chapter, showing its title.
+
+When the `corpus` holds the text of a chapter's verse, the text is shown on the lines under it.
+*/
+pub fn cues(chapters: &[ClipChapter], corpus: Option<&Corpus>) -> Vec<Cue> {
+    chapters
+        .iter()
+        .map(|chapter| Cue {
+            start: chapter.start,
+            end: chapter.end,
+            text: match corpus.and_then(|c| c.text(&chapter.title)) {
+                Some(text) => format!("{}\n{}", chapter.title, text),
+                None => chapter.title.clone(),
+            },
+        })
+        .collect()
+}
+
+/// Writes the cues to `path` in the given format.
+pub fn write(cues: &[Cue], format: SubtitleFormat, path: &Path) -> Result<(), Error> {
+    fs::write(path, format.render(cues))?;
+    Ok(())
+}
+
+/// Returns the cues formatted as a SubRip file.
+pub fn srt(cues: &[Cue]) -> String {
+    cues.iter()
+        .enumerate()
+        .map(|(i, cue)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                i + 1,
+                timestamp(cue.start, ','),
+                timestamp(cue.end, ','),
+                cue.text
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Returns the cues formatted as a WebVTT file.
+pub fn webvtt(cues: &[Cue]) -> String {
+    let mut file = String::from("WEBVTT\n");
+
+    for cue in cues {
+        file.push_str(&format!(
+            "\n{} --> {}\n{}\n",
+            timestamp(cue.start, '.'),
+            timestamp(cue.end, '.'),
+            cue.text
+        ));
+    }
+
+    file
+}
+
+/// Returns the time as `hh:mm:ss` followed by the `separator` and the milliseconds.
+fn timestamp(time: Timestamp, separator: char) -> String {
+    let total: u64 = time.to_ticks("1/1000").unwrap_or_default().max(0) as u64;
+    let (hours, minutes, seconds, millis) = (
+        total / 3_600_000,
+        total / 60_000 % 60,
+        total / 1000 % 60,
+        total % 1000,
+    );
+
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        hours, minutes, seconds, separator, millis
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timestamp::t;
+
+    fn chapters() -> Vec<ClipChapter> {
+        vec![
+            ClipChapter {
+                start: t("0"),
+                end: t("28.662"),
+                title: String::from("John 3:16"),
+            },
+            ClipChapter {
+                start: t("28.662"),
+                end: t("3725.5"),
+                title: String::from("John 3:17"),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(t("28.662"), ','), "00:00:28,662");
+        assert_eq!(timestamp(t("3725.5"), '.'), "01:02:05.500");
+    }
+
+    #[test]
+    fn test_srt() {
+        let corpus = Corpus::from_lines("John 3:16\tFor God loved the world so much\n").unwrap();
+        assert_eq!(
+            srt(&cues(&chapters(), Some(&corpus))),
+            "1\n00:00:00,000 --> 00:00:28,662\nJohn 3:16\nFor God loved the world so much\n\
+             \n2\n00:00:28,662 --> 01:02:05,500\nJohn 3:17\n"
+        );
+    }
+
+    #[test]
+    fn test_webvtt() {
+        assert_eq!(
+            webvtt(&cues(&chapters()[..1], None)),
+            "WEBVTT\n\n00:00:00.000 --> 00:00:28.662\nJohn 3:16\n"
+        );
+    }
+}
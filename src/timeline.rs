@@ -0,0 +1,431 @@
+/*!
+Editor timelines
+
+Exports the chapters of a video file as a timeline an editor can import, with the whole file laid
+on the timeline and a marker at the start of every verse. Three formats are written:
+
+- [CMX3600 EDL](https://xmil.biz/EDL-X/CMX3600.pdf), one event per verse with a locator named after it.
+- [FCPXML](https://developer.apple.com/documentation/professional_video_applications/fcpxml_reference) 1.9,
+  read by Final Cut Pro, DaVinci Resolve and Premiere Pro.
+- [OpenTimelineIO](https://opentimelineio.readthedocs.io/) JSON.
+
+Times are snapped to the frames of the source's video stream. EDL timecodes are non-drop frame.
+*/
+
+use crate::ffwrappers::probe::{Chapter, Root};
+use crate::timestamp::Timestamp;
+use serde_json::{json, Value};
+use std::{fs, io, path::Path, str::FromStr};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The timeline format `{format:?}` is not supported")]
+    UnknownFormat { format: String },
+    #[error("The file has no chapters to mark on a timeline")]
+    NoChapters,
+    #[error("Unable to write the timeline: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// The timeline formats a file's chapters can be exported as.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimelineFormat {
+    Edl,
+    Fcpxml,
+    Otio,
+}
+
+impl FromStr for TimelineFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "edl" => Ok(TimelineFormat::Edl),
+            "fcpxml" => Ok(TimelineFormat::Fcpxml),
+            "otio" => Ok(TimelineFormat::Otio),
+            _ => Err(Error::UnknownFormat {
+                format: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// The frame rate of the source as a fraction, for example 30000/1001.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FrameRate {
+    num: u64,
+    den: u64,
+}
+
+impl FrameRate {
+    /// Returns the frame rate of the first video stream, falling back to 25 fps.
+    fn new(root: &Root) -> FrameRate {
+        let rate: Option<FrameRate> = root
+            .streams
+            .iter()
+            .find(|s| s.codec_type == "video")
+            .and_then(|s| {
+                let (num, den) = s
+                    .r_frame_rate
+                    .split_once('/')
+                    .unwrap_or((&s.r_frame_rate, "1"));
+                Some(FrameRate {
+                    num: num.parse().ok()?,
+                    den: den.parse().ok()?,
+                })
+            });
+
+        match rate {
+            Some(r) if r.num > 0 && r.den > 0 => r,
+            _ => FrameRate { num: 25, den: 1 },
+        }
+    }
+
+    /// Returns the frame nearest to the time.
+    fn frames(&self, time: Timestamp) -> u64 {
+        let frame: String = format!("{}/{}", self.den, self.num);
+        time.to_ticks(&frame).unwrap_or_default().max(0) as u64
+    }
+
+    /// Returns the number of frames counted in each second of a timecode.
+    fn nominal(&self) -> u64 {
+        ((self.num as f64 / self.den as f64).round() as u64).max(1)
+    }
+
+    /// Returns the frame count as a non-drop frame `hh:mm:ss:ff` timecode.
+    fn timecode(&self, frames: u64) -> String {
+        let fps: u64 = self.nominal();
+        let seconds: u64 = frames / fps;
+        format!(
+            "{:02}:{:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60,
+            frames % fps
+        )
+    }
+
+    /// Returns the frame count as an FCPXML rational time such as `1001/30000s`.
+    fn rational(&self, frames: u64) -> String {
+        match frames {
+            0 => String::from("0s"),
+            _ => format!("{}/{}s", frames * self.den, self.num),
+        }
+    }
+
+    /// Returns the frame rate as a number of frames per second.
+    fn per_second(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+}
+
+/// A verse on the timeline, in frames from the start of the file.
+#[derive(Debug, Clone, PartialEq)]
+struct Marker {
+    start: u64,
+    end: u64,
+    title: String,
+}
+
+/// Returns a marker for each chapter of the file.
+fn markers(chapters: &[Chapter], rate: FrameRate) -> Vec<Marker> {
+    chapters
+        .iter()
+        .map(|c| {
+            let (start, end) = c.times();
+            Marker {
+                start: rate.frames(start),
+                end: rate.frames(end),
+                title: c.tags.title.clone(),
+            }
+        })
+        .collect()
+}
+
+/**
+Returns the chapters of `root` as a timeline of the given format.
+
+The `path` of the source is used to name the clip and, for FCPXML and OpenTimelineIO, to link to
+the media, so it should be absolute.
+
+# Example
+```rust, ignore
+let chapters: Root = Root::new("nwt_43_Joh_ASL_03_r720P.mp4")?;
+let edl: String = timeline::export(&chapters, Path::new("/videos/nwt_43_Joh_ASL_03_r720P.mp4"), TimelineFormat::Edl)?;
+```
+*/
+pub fn export(root: &Root, path: &Path, format: TimelineFormat) -> Result<String, Error> {
+    if root.chapters.is_empty() {
+        return Err(Error::NoChapters);
+    }
+    let rate: FrameRate = FrameRate::new(root);
+    let markers: Vec<Marker> = markers(&root.chapters, rate);
+    let duration: u64 = match root.duration() {
+        Some(d) => rate.frames(d),
+        None => markers.iter().map(|m| m.end).max().unwrap_or(0),
+    };
+
+    Ok(match format {
+        TimelineFormat::Edl => edl(root, path, &markers, rate),
+        TimelineFormat::Fcpxml => fcpxml(root, path, &markers, rate, duration),
+        TimelineFormat::Otio => otio(path, &markers, rate, duration),
+    })
+}
+
+/// Writes the chapters of `root` as a timeline to `output`, see [export].
+pub fn write(root: &Root, path: &Path, format: TimelineFormat, output: &Path) -> Result<(), Error> {
+    fs::write(output, export(root, path, format)?)?;
+    Ok(())
+}
+
+/// Returns the file name of the source.
+fn clip_name(path: &Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Returns the timeline as a CMX3600 EDL, one event per verse.
+fn edl(root: &Root, path: &Path, markers: &[Marker], rate: FrameRate) -> String {
+    let name: String = clip_name(path);
+    let channels: &str = if root.has_audio() { "AA/V" } else { "V" };
+    let mut file: String = format!(
+        "TITLE: {}\nFCM: NON-DROP FRAME\n",
+        path.file_stem()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    );
+
+    for (i, marker) in markers.iter().enumerate() {
+        let (start, end) = (rate.timecode(marker.start), rate.timecode(marker.end));
+        file.push_str(&format!(
+            "\n{:03}  AX       {:<4}  C        {} {} {} {}\n",
+            i + 1,
+            channels,
+            start,
+            end,
+            start,
+            end
+        ));
+        file.push_str(&format!("* FROM CLIP NAME: {}\n", name));
+        file.push_str(&format!("* LOC: {} GREEN   {}\n", start, marker.title));
+    }
+
+    file
+}
+
+/// Returns the timeline as FCPXML, the whole file as one clip carrying a marker per verse.
+fn fcpxml(root: &Root, path: &Path, markers: &[Marker], rate: FrameRate, duration: u64) -> String {
+    let name: String = escape(&clip_name(path));
+    let video = root.streams.iter().find(|s| s.codec_type == "video");
+    let (width, height) = (
+        video.and_then(|v| v.width).unwrap_or(1280),
+        video.and_then(|v| v.height).unwrap_or(720),
+    );
+    let length: String = rate.rational(duration);
+    let mut clip_markers = String::new();
+
+    for marker in markers {
+        clip_markers.push_str(&format!(
+            "              <marker start=\"{}\" duration=\"{}\" value=\"{}\"/>\n",
+            rate.rational(marker.start),
+            rate.rational(1),
+            escape(&marker.title)
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE fcpxml>
+<fcpxml version="1.9">
+  <resources>
+    <format id="r1" frameDuration="{frame}" width="{width}" height="{height}"/>
+    <asset id="r2" name="{name}" start="0s" duration="{length}" hasVideo="1" hasAudio="{audio}" format="r1">
+      <media-rep kind="original-media" src="{url}"/>
+    </asset>
+  </resources>
+  <library>
+    <event name="{name}">
+      <project name="{name}">
+        <sequence format="r1" duration="{length}" tcStart="0s" tcFormat="NDF">
+          <spine>
+            <asset-clip ref="r2" name="{name}" offset="0s" start="0s" duration="{length}" format="r1">
+{markers}            </asset-clip>
+          </spine>
+        </sequence>
+      </project>
+    </event>
+  </library>
+</fcpxml>
+"#,
+        frame = rate.rational(1),
+        width = width,
+        height = height,
+        name = name,
+        length = length,
+        audio = if root.has_audio() { 1 } else { 0 },
+        url = escape(&file_url(path)),
+        markers = clip_markers
+    )
+}
+
+/// Returns the timeline as OpenTimelineIO JSON, the whole file as one clip carrying a marker per verse.
+fn otio(path: &Path, markers: &[Marker], rate: FrameRate, duration: u64) -> String {
+    let range = |start: u64, length: u64| {
+        json!({
+            "OTIO_SCHEMA": "TimeRange.1",
+            "start_time": { "OTIO_SCHEMA": "RationalTime.1", "rate": rate.per_second(), "value": start as f64 },
+            "duration": { "OTIO_SCHEMA": "RationalTime.1", "rate": rate.per_second(), "value": length as f64 },
+        })
+    };
+    let name: String = clip_name(path);
+    let clip_markers: Vec<Value> = markers
+        .iter()
+        .map(|m| {
+            json!({
+                "OTIO_SCHEMA": "Marker.2",
+                "name": m.title,
+                "color": "GREEN",
+                "comment": "",
+                "marked_range": range(m.start, m.end.saturating_sub(m.start)),
+                "metadata": {},
+            })
+        })
+        .collect();
+
+    let timeline: Value = json!({
+        "OTIO_SCHEMA": "Timeline.1",
+        "name": name,
+        "global_start_time": null,
+        "metadata": {},
+        "tracks": {
+            "OTIO_SCHEMA": "Stack.1",
+            "name": "tracks",
+            "source_range": null,
+            "effects": [],
+            "markers": [],
+            "metadata": {},
+            "children": [{
+                "OTIO_SCHEMA": "Track.1",
+                "name": "V1",
+                "kind": "Video",
+                "source_range": null,
+                "effects": [],
+                "markers": [],
+                "metadata": {},
+                "children": [{
+                    "OTIO_SCHEMA": "Clip.1",
+                    "name": name,
+                    "source_range": range(0, duration),
+                    "effects": [],
+                    "markers": clip_markers,
+                    "metadata": {},
+                    "media_reference": {
+                        "OTIO_SCHEMA": "ExternalReference.1",
+                        "target_url": file_url(path),
+                        "available_range": range(0, duration),
+                        "metadata": {},
+                    },
+                }],
+            }],
+        },
+    });
+
+    serde_json::to_string_pretty(&timeline).unwrap()
+}
+
+/// Returns the path as a `file://` URL, percent encoding anything but unreserved characters and slashes.
+fn file_url(path: &Path) -> String {
+    let mut url = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    url
+}
+
+/// Escapes the characters special to XML attributes.
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffwrappers::probe::fixtures::init_struct_1;
+
+    #[test]
+    fn test_frame_rate() {
+        let rate = FrameRate::new(&init_struct_1());
+        assert_eq!(rate.frames("12.178833".parse().unwrap()), 365);
+        assert_eq!(rate.timecode(365), "00:00:12:05");
+        assert_eq!(rate.rational(365), "365365/30000s");
+        assert_eq!(FrameRate::new(&Root::default()).nominal(), 25);
+    }
+
+    #[test]
+    fn test_edl() {
+        let edl = export(
+            &init_struct_1(),
+            Path::new("/videos/nwt_43_Joh_ASL_03_r720P.mp4"),
+            TimelineFormat::Edl,
+        )
+        .unwrap();
+        assert!(edl.starts_with("TITLE: nwt_43_Joh_ASL_03_r720P\nFCM: NON-DROP FRAME\n"));
+        assert!(edl.contains(
+            "\n002  AX       AA/V  C        00:03:46:01 00:04:01:20 00:03:46:01 00:04:01:20\n\
+             * FROM CLIP NAME: nwt_43_Joh_ASL_03_r720P.mp4\n\
+             * LOC: 00:03:46:01 GREEN   John 3:17\n"
+        ));
+    }
+
+    #[test]
+    fn test_fcpxml() {
+        let xml = export(
+            &init_struct_1(),
+            Path::new("/videos/John 3.mp4"),
+            TimelineFormat::Fcpxml,
+        )
+        .unwrap();
+        assert!(xml.contains("src=\"file:///videos/John%203.mp4\""));
+        assert!(xml.contains("duration=\"11999988/30000s\""));
+        assert!(xml.contains(
+            "<marker start=\"6787781/30000s\" duration=\"1001/30000s\" value=\"John 3:17\"/>"
+        ));
+    }
+
+    #[test]
+    fn test_otio() {
+        let otio = export(
+            &init_struct_1(),
+            Path::new("/videos/a.mp4"),
+            TimelineFormat::Otio,
+        )
+        .unwrap();
+        let timeline: Value = serde_json::from_str(&otio).unwrap();
+        let clip: &Value = &timeline["tracks"]["children"][0]["children"][0];
+        assert_eq!(clip["markers"][1]["name"], "John 3:17");
+        assert_eq!(
+            clip["markers"][1]["marked_range"]["start_time"]["value"],
+            6781.0
+        );
+        assert_eq!(
+            clip["media_reference"]["target_url"],
+            "file:///videos/a.mp4"
+        );
+    }
+
+    #[test]
+    fn test_no_chapters() {
+        assert!(export(&Root::default(), Path::new("a.mp4"), TimelineFormat::Edl).is_err());
+    }
+}
@@ -0,0 +1,443 @@
+/*!
+Exact timestamps
+
+ffprobe reports every chapter boundary twice: as a whole number of ticks in the chapter's `time_base`,
+and as the decimal `start_time` and `end_time` strings rounded from them. A [Timestamp] keeps a time as
+an exact fraction of a second built from the ticks, so adding padding or measuring a segment never
+accumulates the rounding errors of floating point.
+
+Times are written for ffmpeg in whole microseconds, the resolution ffmpeg reads them at, rounded once
+from the exact value so a cut never lands on the neighbouring frame.
+*/
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    iter::Sum,
+    ops::{Add, Div, Mul, Sub},
+    str::FromStr,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("The time base `{time_base:?}` is not a fraction such as 1/1000")]
+    TimeBase { time_base: String },
+    #[error("The time `{time:?}` is not a number of seconds")]
+    Malformed { time: String },
+}
+
+/// The number of microseconds in a second, the resolution ffmpeg reads times at.
+const MICROSECONDS: i128 = 1_000_000;
+
+/// The most decimal places read from a time, enough for any time ffprobe reports.
+const MAX_DECIMALS: usize = 18;
+
+/**
+A time in seconds, kept as an exact fraction.
+
+## Example
+
+```rust, ignore
+let start = Timestamp::from_ticks(17781930, "1/90000")?;
+assert_eq!(start.to_string(), "197.577");
+```
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timestamp {
+    num: i128,
+    den: i128,
+}
+
+impl Timestamp {
+    /// The start of the file.
+    pub const ZERO: Timestamp = Timestamp { num: 0, den: 1 };
+
+    /// One millisecond.
+    pub const MILLISECOND: Timestamp = Timestamp { num: 1, den: 1000 };
+
+    /// One microsecond, the resolution ffprobe reports times at.
+    pub const MICROSECOND: Timestamp = Timestamp {
+        num: 1,
+        den: MICROSECONDS,
+    };
+
+    /// Returns the time of `ticks` in a time base such as `1/1000`.
+    pub fn from_ticks(ticks: i64, time_base: &str) -> Result<Timestamp, Error> {
+        let error = || Error::TimeBase {
+            time_base: time_base.to_string(),
+        };
+        let (num, den) = time_base.split_once('/').ok_or_else(error)?;
+        let num: i128 = num.trim().parse().map_err(|_| error())?;
+        let den: i128 = den.trim().parse().map_err(|_| error())?;
+        if num <= 0 || den <= 0 {
+            return Err(error());
+        }
+
+        Ok(Timestamp::reduced(ticks as i128 * num, den))
+    }
+
+    /// Returns the time as the nearest whole number of ticks in a time base such as `1/1000`.
+    pub fn to_ticks(&self, time_base: &str) -> Result<i64, Error> {
+        let tick: Timestamp = Timestamp::from_ticks(1, time_base)?;
+        Ok(rounded_div(self.num * tick.den, self.den * tick.num) as i64)
+    }
+
+    /// Returns the time nearest to `seconds`, to the microsecond. Used for times typed on the command line.
+    pub fn from_seconds(seconds: f64) -> Timestamp {
+        Timestamp::reduced(
+            (seconds * MICROSECONDS as f64).round() as i128,
+            MICROSECONDS,
+        )
+    }
+
+    /**
+    Parses a time typed on the command line, either as `hh:mm:ss.mmm`, `mm:ss.mmm` or a number of seconds.
+
+    ## Example
+
+    ```rust, ignore
+    assert_eq!(Timestamp::from_clock("1:02:03.5")?, Timestamp::from_clock("3723.5")?);
+    ```
+    */
+    pub fn from_clock(time: &str) -> Result<Timestamp, Error> {
+        let error = || Error::Malformed {
+            time: time.to_string(),
+        };
+        let parts: Vec<&str> = time.trim().split(':').collect();
+        if parts.len() > 3 || parts.iter().any(|p| p.starts_with('-')) {
+            return Err(error());
+        }
+
+        let (seconds, units) = parts.split_last().ok_or_else(error)?;
+        let mut total: Timestamp = seconds.parse().map_err(|_| error())?;
+        for (unit, scale) in units.iter().rev().zip([60, 3600]) {
+            let unit: i128 = unit.parse().map_err(|_| error())?;
+            total = total + Timestamp::reduced(unit * scale, 1);
+        }
+        Ok(total)
+    }
+
+    /// Returns how far the time is from zero.
+    pub fn abs(&self) -> Timestamp {
+        Timestamp {
+            num: self.num.abs(),
+            den: self.den,
+        }
+    }
+
+    /// Returns the time in seconds, for the places an approximate time is enough.
+    pub fn seconds(&self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+
+    /// Returns the fraction `num / den` in lowest terms, with a positive denominator.
+    fn reduced(num: i128, den: i128) -> Timestamp {
+        let divisor: i128 = gcd(num, den).max(1) * den.signum();
+        Timestamp {
+            num: num / divisor,
+            den: den / divisor,
+        }
+    }
+}
+
+impl Default for Timestamp {
+    fn default() -> Self {
+        Timestamp::ZERO
+    }
+}
+
+impl Ord for Timestamp {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.num * other.den).cmp(&(other.num * self.den))
+    }
+}
+
+impl PartialOrd for Timestamp {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add for Timestamp {
+    type Output = Timestamp;
+
+    fn add(self, other: Timestamp) -> Timestamp {
+        Timestamp::reduced(
+            self.num * other.den + other.num * self.den,
+            self.den * other.den,
+        )
+    }
+}
+
+impl Sub for Timestamp {
+    type Output = Timestamp;
+
+    fn sub(self, other: Timestamp) -> Timestamp {
+        Timestamp::reduced(
+            self.num * other.den - other.num * self.den,
+            self.den * other.den,
+        )
+    }
+}
+
+impl Mul<i64> for Timestamp {
+    type Output = Timestamp;
+
+    fn mul(self, times: i64) -> Timestamp {
+        Timestamp::reduced(self.num * times as i128, self.den)
+    }
+}
+
+/// Divides the time into `parts` equal parts. `parts` must not be zero.
+impl Div<i64> for Timestamp {
+    type Output = Timestamp;
+
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, parts: i64) -> Timestamp {
+        Timestamp::reduced(self.num, self.den * parts as i128)
+    }
+}
+
+impl Sum for Timestamp {
+    fn sum<I: Iterator<Item = Timestamp>>(iter: I) -> Timestamp {
+        iter.fold(Timestamp::ZERO, Add::add)
+    }
+}
+
+/// Parses a decimal number of seconds, such as the `197.597000` ffprobe reports, exactly.
+impl FromStr for Timestamp {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || Error::Malformed {
+            time: s.to_string(),
+        };
+        let time: &str = s.trim();
+        let (negative, time) = match time.strip_prefix('-') {
+            Some(t) => (true, t),
+            None => (false, time),
+        };
+        let (whole, fraction) = time.split_once('.').unwrap_or((time, ""));
+        let digits = |d: &str| d.chars().all(|c| c.is_ascii_digit());
+        if whole.is_empty() && fraction.is_empty()
+            || !digits(whole)
+            || !digits(fraction)
+            || fraction.len() > MAX_DECIMALS
+        {
+            return Err(error());
+        }
+
+        let den: i128 = 10i128.pow(fraction.len() as u32);
+        let whole: i128 = match whole {
+            "" => 0,
+            w => w.parse().map_err(|_| error())?,
+        };
+        let fraction: i128 = match fraction {
+            "" => 0,
+            f => f.parse().map_err(|_| error())?,
+        };
+        let num: i128 = whole * den + fraction;
+
+        Ok(Timestamp::reduced(if negative { -num } else { num }, den))
+    }
+}
+
+/**
+Writes the time in seconds rounded to the microsecond, without trailing zeros, for example `197.597`.
+
+A precision rounds to that many decimal places instead and keeps the zeros, so `{:.6}` writes
+`197.597000` the way ffprobe reports times.
+*/
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let decimals: usize = f.precision().unwrap_or(6).min(MAX_DECIMALS);
+        let scale: i128 = 10i128.pow(decimals as u32);
+        let scaled: i128 = rounded_div(self.num * scale, self.den);
+        let sign: &str = if scaled < 0 { "-" } else { "" };
+        let (whole, fraction) = (scaled.abs() / scale, scaled.abs() % scale);
+        let fraction: String = match decimals {
+            0 => String::new(),
+            _ => format!("{:0width$}", fraction, width = decimals),
+        };
+        let fraction: &str = match f.precision() {
+            Some(_) => &fraction,
+            None => fraction.trim_end_matches('0'),
+        };
+
+        match fraction.is_empty() {
+            true => write!(f, "{}{}", sign, whole),
+            false => write!(f, "{}{}.{}", sign, whole, fraction),
+        }
+    }
+}
+
+/// Returns `num / den` rounded to the nearest whole number, halves away from zero. `den` must be positive.
+fn rounded_div(num: i128, den: i128) -> i128 {
+    match num < 0 {
+        true => -((-num + den / 2) / den),
+        false => (num + den / 2) / den,
+    }
+}
+
+/// Returns the greatest common divisor of `a` and `b`.
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Parses a time such as `197.597`, for the tests of the modules working with times.
+#[cfg(test)]
+pub(crate) fn t(time: &str) -> Timestamp {
+    time.parse().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_ticks() {
+        assert_eq!(
+            Timestamp::from_ticks(197597, "1/1000").unwrap(),
+            "197.597".parse::<Timestamp>().unwrap()
+        );
+        assert_eq!(
+            Timestamp::from_ticks(1096095, "1/90000")
+                .unwrap()
+                .to_string(),
+            "12.178833"
+        );
+        assert!(Timestamp::from_ticks(1, "0/0").is_err());
+        assert!(Timestamp::from_ticks(1, "").is_err());
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(
+            "197.597000".parse::<Timestamp>().unwrap(),
+            "197.597".parse::<Timestamp>().unwrap()
+        );
+        assert_eq!(
+            "-1.5".parse::<Timestamp>().unwrap(),
+            Timestamp::ZERO - "1.5".parse::<Timestamp>().unwrap()
+        );
+        assert_eq!(
+            ".5".parse::<Timestamp>().unwrap(),
+            "0.5".parse::<Timestamp>().unwrap()
+        );
+        assert!("".parse::<Timestamp>().is_err());
+        assert!("1e3".parse::<Timestamp>().is_err());
+        assert!("1.2.3".parse::<Timestamp>().is_err());
+    }
+
+    #[test]
+    fn test_from_clock() {
+        assert_eq!(
+            Timestamp::from_clock("1:02:03.5").unwrap(),
+            "3723.5".parse::<Timestamp>().unwrap()
+        );
+        assert_eq!(
+            Timestamp::from_clock("03:16.250").unwrap(),
+            "196.25".parse::<Timestamp>().unwrap()
+        );
+        assert_eq!(
+            Timestamp::from_clock("197.597").unwrap(),
+            "197.597".parse::<Timestamp>().unwrap()
+        );
+        assert!(Timestamp::from_clock("1:2:3:4").is_err());
+        assert!(Timestamp::from_clock("-1:00").is_err());
+        assert!(Timestamp::from_clock("a:00").is_err());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        // 0.1 + 0.2 is exactly 0.3, unlike with floating point.
+        assert_eq!(
+            "0.1".parse::<Timestamp>().unwrap() + "0.2".parse::<Timestamp>().unwrap(),
+            "0.3".parse::<Timestamp>().unwrap()
+        );
+        let third = Timestamp::from_ticks(30000, "1/90000").unwrap();
+        assert_eq!(
+            third + third + third,
+            Timestamp::from_ticks(1, "1/1").unwrap()
+        );
+        assert_eq!(
+            "241.908".parse::<Timestamp>().unwrap() - "226.259".parse::<Timestamp>().unwrap(),
+            "15.649".parse::<Timestamp>().unwrap()
+        );
+        assert_eq!(
+            "28.662".parse::<Timestamp>().unwrap() / 6 * 3,
+            "14.331".parse::<Timestamp>().unwrap()
+        );
+        assert_eq!(
+            "1".parse::<Timestamp>().unwrap() / -4,
+            "-0.25".parse::<Timestamp>().unwrap()
+        );
+        assert!("226.259".parse::<Timestamp>().unwrap() < "226.2591".parse::<Timestamp>().unwrap());
+        assert_eq!(
+            vec![
+                "1.5".parse::<Timestamp>().unwrap(),
+                "2.25".parse::<Timestamp>().unwrap()
+            ]
+            .into_iter()
+            .sum::<Timestamp>(),
+            "3.75".parse::<Timestamp>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            "197.597000".parse::<Timestamp>().unwrap().to_string(),
+            "197.597"
+        );
+        assert_eq!("12".parse::<Timestamp>().unwrap().to_string(), "12");
+        assert_eq!("-0.25".parse::<Timestamp>().unwrap().to_string(), "-0.25");
+        assert_eq!(
+            Timestamp::from_ticks(1001, "1/30000").unwrap().to_string(),
+            "0.033367"
+        );
+        assert_eq!(Timestamp::from_seconds(2.5).to_string(), "2.5");
+        assert_eq!(
+            format!("{:.6}", "250".parse::<Timestamp>().unwrap()),
+            "250.000000"
+        );
+        assert_eq!(
+            format!("{:.3}", "12.1788335".parse::<Timestamp>().unwrap()),
+            "12.179"
+        );
+        assert_eq!(format!("{:.0}", "-0.5".parse::<Timestamp>().unwrap()), "-1");
+    }
+
+    #[test]
+    fn test_to_ticks() {
+        assert_eq!(
+            "197.597"
+                .parse::<Timestamp>()
+                .unwrap()
+                .to_ticks("1/1000")
+                .unwrap(),
+            197597
+        );
+        let exact = Timestamp::from_ticks(1096095, "1/90000").unwrap();
+        assert_eq!(exact.to_ticks("1/90000").unwrap(), 1096095);
+        assert_eq!(exact.to_ticks("1/1000").unwrap(), 12179);
+        assert!(exact.to_ticks("1/0").is_err());
+    }
+
+    #[test]
+    fn test_abs() {
+        assert_eq!(
+            "-1.5".parse::<Timestamp>().unwrap().abs(),
+            "1.5".parse::<Timestamp>().unwrap()
+        );
+        assert_eq!(
+            "0.000001".parse::<Timestamp>().unwrap(),
+            Timestamp::MICROSECOND
+        );
+    }
+}